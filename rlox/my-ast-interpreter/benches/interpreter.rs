@@ -0,0 +1,66 @@
+// Rough timings for variable-heavy workloads: `cargo bench -p my-ast-interpreter`.
+use my_ast_interpreter::{Interpreter, Parser, Resolver, Scanner, Sink};
+use std::{io, time::Instant};
+
+const FIB: &str = "
+fun fib(n) {
+  if (n < 2) return n;
+  return fib(n - 1) + fib(n - 2);
+}
+print fib(25);
+";
+
+const CLOSURES: &str = "
+fun counter() {
+  var count = 0;
+  fun increment() {
+    count = count + 1;
+    return count;
+  }
+  return increment;
+}
+
+var total = 0;
+for (var i = 0; i < 2000; i = i + 1) {
+  var next = counter();
+  for (var j = 0; j < 100; j = j + 1) {
+    total = total + next();
+  }
+}
+print total;
+";
+
+const RUNS: u32 = 5;
+
+fn run(source: &str) {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    assert!(errors.is_empty(), "{errors:?}");
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::new(io::sink()));
+    Resolver::new(&mut interpreter)
+        .resolve(&statements)
+        .expect("should resolve");
+    interpreter
+        .set_status("run")
+        .expect("should set interpreter status::run");
+    interpreter.interpret(statements).expect("should run");
+}
+
+fn bench(name: &str, source: &str) {
+    run(source); // warm up
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        run(source);
+    }
+
+    println!("{name:<10} {:>10.2?} / run", start.elapsed() / RUNS);
+}
+
+fn main() {
+    bench("fib", FIB);
+    bench("closures", CLOSURES);
+}
@@ -0,0 +1,212 @@
+use crate::{Callable, Expr, Stmt, Token};
+
+/// Prints a parsed program as S-expressions, one top-level statement per line, the way jlox's
+/// `AstPrinter` does: `print 1 + 2 * 3;` becomes `(print (+ 1.0 (* 2.0 3.0)))`. Unlike the
+/// formatter this shows how the parser grouped things, so it's meant for debugging the parser
+/// rather than for reading programs.
+pub fn print(statements: &[Stmt]) -> String {
+    statements
+        .iter()
+        .map(|statement| format!("{}\n", stmt(statement)))
+        .collect()
+}
+
+pub fn stmt(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::Expression(expression, _) => parenthesize(";", [expr(expression)]),
+        Stmt::Print(_, expression) => parenthesize("print", [expr(expression)]),
+        Stmt::Var(name, Expr::Nil) => parenthesize("var", [name.lexeme.clone()]),
+        Stmt::Var(name, initializer) => parenthesize(
+            "var",
+            [name.lexeme.clone(), "=".to_string(), expr(initializer)],
+        ),
+        Stmt::Block(statements, _) => parenthesize("block", statements.iter().map(self::stmt)),
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch: None,
+            ..
+        } => parenthesize("if", [expr(condition), self::stmt(then_branch)]),
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => parenthesize(
+            "if-else",
+            [
+                expr(condition),
+                self::stmt(then_branch),
+                self::stmt(else_branch),
+            ],
+        ),
+        Stmt::While {
+            condition, body, ..
+        } => parenthesize("while", [expr(condition), self::stmt(body)]),
+        // a missing clause prints as `_`
+        Stmt::For {
+            initializer,
+            condition,
+            increment,
+            body,
+            ..
+        } => parenthesize(
+            "for",
+            [
+                initializer
+                    .as_deref()
+                    .map_or_else(|| "_".to_string(), self::stmt),
+                condition.as_ref().map_or_else(|| "_".to_string(), expr),
+                increment.as_ref().map_or_else(|| "_".to_string(), expr),
+                self::stmt(body),
+            ],
+        ),
+        Stmt::Function(function) => self::function(function),
+        Stmt::Class {
+            name,
+            superclass,
+            methods,
+            ..
+        } => {
+            let mut parts = vec![name.lexeme.clone()];
+            if let Some(superclass) = superclass {
+                parts.push("<".to_string());
+                parts.push(expr(superclass));
+            }
+            parts.extend(methods.iter().map(self::function));
+
+            parenthesize("class", parts)
+        }
+        Stmt::Import {
+            path,
+            name: Some(name),
+            ..
+        } => parenthesize(
+            "import",
+            [format!("\"{path}\""), "as".to_string(), name.lexeme.clone()],
+        ),
+        Stmt::Import {
+            path, name: None, ..
+        } => parenthesize("import", [format!("\"{path}\"")]),
+        Stmt::Return(_, None) => "(return)".to_string(),
+        Stmt::Return(_, Some(value)) => parenthesize("return", [expr(value)]),
+        Stmt::Throw(_, value) => parenthesize("throw", [expr(value)]),
+        Stmt::Try {
+            body,
+            catch,
+            finally,
+            ..
+        } => {
+            let mut parts = vec![self::stmt(body)];
+            if let Some((name, handler)) = catch {
+                parts.push(parenthesize(
+                    "catch",
+                    [name.lexeme.clone(), self::stmt(handler)],
+                ));
+            }
+            if let Some(finally) = finally {
+                parts.push(parenthesize("finally", [self::stmt(finally)]));
+            }
+
+            parenthesize("try", parts)
+        }
+        Stmt::Break(_) => "(break)".to_string(),
+        Stmt::Continue(_) => "(continue)".to_string(),
+    }
+}
+
+// function declarations and methods
+fn function(function: &Callable) -> String {
+    let Callable::Function {
+        name, params, body, ..
+    } = function
+    else {
+        unreachable!("declarations only hold `Callable::Function`")
+    };
+
+    parenthesize(
+        &format!("fun {}", name.lexeme),
+        [params_list(params), stmt(body)],
+    )
+}
+
+fn params_list(params: &[Token]) -> String {
+    let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+    format!("({})", params.join(" "))
+}
+
+pub fn expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => format!("{n:?}"),
+        Expr::String(s) => format!("\"{s}\""),
+        Expr::Bool(b) => b.to_string(),
+        Expr::Nil => "nil".to_string(),
+        Expr::Grouping(expression) => parenthesize("group", [self::expr(expression)]),
+        Expr::Unary { operator, right } => parenthesize(&operator.lexeme, [self::expr(right)]),
+        Expr::Binary {
+            operator,
+            left,
+            right,
+        }
+        | Expr::Logical {
+            operator,
+            left,
+            right,
+        } => parenthesize(&operator.lexeme, [self::expr(left), self::expr(right)]),
+        Expr::Variable(name) => name.lexeme.clone(),
+        Expr::Assign(name, value) => parenthesize("=", [name.lexeme.clone(), self::expr(value)]),
+        Expr::Call {
+            callee, arguments, ..
+        } => parenthesize(
+            "call",
+            std::iter::once(self::expr(callee)).chain(arguments.iter().map(|arg| self::expr(arg))),
+        ),
+        Expr::Get { object, name } => parenthesize(".", [self::expr(object), name.lexeme.clone()]),
+        Expr::Set {
+            object,
+            name,
+            value,
+        } => parenthesize(
+            "=",
+            [self::expr(object), name.lexeme.clone(), self::expr(value)],
+        ),
+        Expr::This(_) => "this".to_string(),
+        Expr::Super { method, .. } => parenthesize("super", [method.lexeme.clone()]),
+        Expr::Function { params, body, .. } => {
+            parenthesize("fun", [params_list(params), stmt(body)])
+        }
+        Expr::Interpolation(parts) => parenthesize("str", parts.iter().map(self::expr)),
+        Expr::List { elements, .. } => parenthesize("list", elements.iter().map(self::expr)),
+        Expr::Map { entries, .. } => parenthesize(
+            "map",
+            entries
+                .iter()
+                .map(|(key, value)| format!("({} {})", self::expr(key), self::expr(value))),
+        ),
+        Expr::Index { object, index, .. } => {
+            parenthesize("index", [self::expr(object), self::expr(index)])
+        }
+        Expr::SetIndex {
+            object,
+            index,
+            value,
+            ..
+        } => parenthesize(
+            "=",
+            [
+                parenthesize("index", [self::expr(object), self::expr(index)]),
+                self::expr(value),
+            ],
+        ),
+    }
+}
+
+fn parenthesize(name: &str, parts: impl IntoIterator<Item = String>) -> String {
+    let mut out = format!("({name}");
+    for part in parts {
+        out.push(' ');
+        out.push_str(&part);
+    }
+    out.push(')');
+    out
+}
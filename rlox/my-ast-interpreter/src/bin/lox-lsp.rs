@@ -0,0 +1,13 @@
+use my_ast_interpreter::lsp;
+use std::io;
+use std::process;
+
+// language server for editors, talking JSON-RPC over stdin/stdout
+fn main() {
+    let code = lsp::run(io::stdin().lock(), io::stdout().lock()).unwrap_or_else(|err| {
+        eprintln!("lox-lsp: {err}");
+        1
+    });
+
+    process::exit(code);
+}
@@ -1,5 +1,35 @@
-use crate::{Environment, ExprValue, Interpreter, RuntimeError, Stmt, Token};
-use std::{cell::RefCell, rc::Rc};
+use crate::{
+    Class, ControlFlow, Environment, ExprValue, Instance, Interpreter, Local, RuntimeError, Stmt,
+    Token,
+};
+use std::{cell::RefCell, fmt, rc::Rc};
+
+pub type NativeFn = dyn Fn(&mut Interpreter, &[ExprValue]) -> Result<ExprValue, RuntimeError>;
+
+// host-defined function body, compared by identity since closures have no structural equality
+#[derive(Clone)]
+pub struct NativeFunction(Rc<NativeFn>);
+
+impl NativeFunction {
+    pub fn new<F>(func: F) -> Self
+    where
+        F: Fn(&mut Interpreter, &[ExprValue]) -> Result<ExprValue, RuntimeError> + 'static,
+    {
+        NativeFunction(Rc::new(func))
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeFunction")
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Callable {
@@ -8,25 +38,22 @@ pub enum Callable {
         params: Vec<Token>,
         body: Box<Stmt>,
         closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    },
+    Class(Rc<Class>),
+    NativeFn {
+        name: String,
+        arity: usize,
+        func: NativeFunction,
     },
-    // NativeFn {
-    //     name: String,
-    //     params: &[ExprValue],
-    //     closure: Rc<RefCell<Environment>>,
-    //     func: fn(&[ExprValue]) -> Result<ExprValue, RuntimeError>,
-    // },
-    // Method {
-    //     name: Token,
-    //     params: Vec<Token>,
-    //     body: Vec<Stmt>,
-    // },
 }
 
 impl Callable {
     pub fn arity(&self) -> usize {
         match self {
             Callable::Function { params, .. } => params.len(),
-            // Callable::NativeFn {params, ..} => params.len(),
+            Callable::Class(class) => class.find_method("init").map_or(0, |init| init.arity()),
+            Callable::NativeFn { arity, .. } => *arity,
         }
     }
 
@@ -40,37 +67,106 @@ impl Callable {
                 params,
                 body,
                 closure,
+                is_initializer,
                 ..
             } => {
-                // let mut call_environment =
-                // Environment::with_enclosing(interpreter.environment.clone());
                 let mut call_environment = Environment::with_enclosing(closure.clone());
 
                 for (param, arg) in params.iter().zip(arguments.iter()) {
-                    call_environment.define(param.lexeme.clone(), arg.clone());
+                    call_environment.define(&param.lexeme, arg.clone());
                 }
-                let prev_environment = interpreter.environment.clone();
-                interpreter.environment = Rc::new(RefCell::new(call_environment));
-
-                // match interpreter.eval_block_stmt(&[*body.clone()], Some(call_environment)) {
-                let eval_result = match interpreter.eval_block_stmt(&[*body.clone()], None) {
-                    Ok(_) => Ok(ExprValue::Nil),
-                    Err(RuntimeError::Return(return_val)) => Ok(return_val),
-                    Err(e) => Err(e),
+
+                // body is parsed as a block: run its statements directly in the call env
+                // so runtime scopes line up with the resolver's (params + body = one scope each)
+                let statements = match body.as_ref() {
+                    Stmt::Block(statements, _) => statements.as_slice(),
+                    stmt => std::slice::from_ref(stmt),
                 };
 
-                interpreter.environment = prev_environment;
+                let eval_result =
+                    match interpreter.eval_block_stmt(statements, Some(call_environment)) {
+                        Ok(ControlFlow::Return(return_val)) => Ok(return_val),
+                        Ok(_) => Ok(ExprValue::Nil),
+                        Err(e) => Err(e),
+                    };
+
+                // initializers always hand back `this`, even on an early `return;`
+                if *is_initializer {
+                    let this = Local { depth: 0, slot: 0 };
+                    return eval_result.map(|_| closure.borrow().get_at(this));
+                }
+
                 eval_result
             }
+            Callable::Class(class) => {
+                let instance = Rc::new(RefCell::new(Instance::new(class.clone())));
+
+                if let Some(initializer) = class.find_method("init") {
+                    initializer
+                        .bind(instance.clone())
+                        .call(interpreter, arguments)?;
+                }
+
+                Ok(ExprValue::Instance(instance))
+            }
+            Callable::NativeFn { func, .. } => (func.0)(interpreter, &arguments),
+        }
+    }
+
+    // `==` on callables: the same class or native, or the same declaration closing over the same
+    // environment (so each `instance.method` access binds a new, unequal method)
+    pub fn is_same(&self, other: &Callable) -> bool {
+        match (self, other) {
+            (
+                Callable::Function {
+                    name: a,
+                    closure: a_closure,
+                    ..
+                },
+                Callable::Function {
+                    name: b,
+                    closure: b_closure,
+                    ..
+                },
+            ) => a.span == b.span && Rc::ptr_eq(a_closure, b_closure),
+            (Callable::Class(a), Callable::Class(b)) => Rc::ptr_eq(a, b),
+            (Callable::NativeFn { func: a, .. }, Callable::NativeFn { func: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+
+    // wraps a method's closure in a new env where `this` is bound to the given instance
+    pub fn bind(&self, instance: Rc<RefCell<Instance>>) -> Callable {
+        match self {
+            Callable::Function {
+                name,
+                params,
+                body,
+                closure,
+                is_initializer,
+            } => {
+                let mut environment = Environment::with_enclosing(closure.clone());
+                environment.define("this", ExprValue::Instance(instance));
+
+                Callable::Function {
+                    name: name.clone(),
+                    params: params.clone(),
+                    body: body.clone(),
+                    closure: environment.into_shared(),
+                    is_initializer: *is_initializer,
+                }
+            }
+            _ => unreachable!("only methods can be bound to an instance"),
         }
     }
 }
 
-impl std::fmt::Display for Callable {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for Callable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Callable::Function { name, .. } => write!(f, "<fn {}>", name.lexeme),
-            // Callable::NativeFn {name, .. } => write!(f, "<native fn {}>", name.lexeme)
+            Callable::Class(class) => write!(f, "{}", class),
+            Callable::NativeFn { name, .. } => write!(f, "<native fn {}>", name),
         }
     }
 }
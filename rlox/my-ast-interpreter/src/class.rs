@@ -0,0 +1,105 @@
+use crate::{Callable, ExprValue, RuntimeError, Token};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+// class stores behavior (map of methods), instance stores state (map of fields)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub name: String,
+    pub superclass: Option<Rc<Class>>,
+    methods: HashMap<String, Callable>,
+}
+
+impl Class {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<Class>>,
+        methods: HashMap<String, Callable>,
+    ) -> Self {
+        Class {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    pub(crate) fn methods(&self) -> impl Iterator<Item = &Callable> {
+        self.methods.values()
+    }
+
+    // walks up the inheritance chain when a method isn't defined on this class
+    pub fn find_method(&self, name: &str) -> Option<&Callable> {
+        self.methods.get(name).or_else(|| {
+            self.superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name))
+        })
+    }
+}
+
+impl std::fmt::Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub class: Rc<Class>,
+    fields: HashMap<String, ExprValue>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+        Instance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    // fields shadow methods; methods are bound to the instance they're accessed from
+    pub fn get(instance: &Rc<RefCell<Instance>>, name: &Token) -> Result<ExprValue, RuntimeError> {
+        if let Some(val) = instance.borrow().fields.get(&name.lexeme) {
+            return Ok(val.clone());
+        }
+
+        let method = instance.borrow().class.find_method(&name.lexeme).cloned();
+
+        if let Some(method) = method {
+            return Ok(ExprValue::Call(method.bind(instance.clone())));
+        }
+
+        Err(RuntimeError::RTE {
+            token: name.lexeme.clone(),
+            message: format!("Undefined property '{}'.", name.lexeme),
+            line: name.line,
+            span: name.span,
+        })
+    }
+
+    pub fn set(&mut self, name: &Token, value: ExprValue) {
+        self.fields.insert(name.lexeme.clone(), value);
+    }
+
+    // field access for the host side, which has names rather than tokens
+    pub fn field(&self, name: &str) -> Option<&ExprValue> {
+        self.fields.get(name)
+    }
+
+    pub fn set_field(&mut self, name: &str, value: ExprValue) {
+        self.fields.insert(name.to_string(), value);
+    }
+
+    pub(crate) fn fields(&self) -> impl Iterator<Item = &ExprValue> {
+        self.fields.values()
+    }
+
+    pub(crate) fn clear_fields(&mut self) {
+        self.fields.clear();
+    }
+}
+
+impl std::fmt::Display for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
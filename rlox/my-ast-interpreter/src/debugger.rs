@@ -0,0 +1,342 @@
+use crate::{Environment, ExprValue, Interpreter, Parser, Resolver, RuntimeError, Scanner, Stmt};
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    fmt,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+/// Called by the interpreter before every statement it executes (see `Interpreter::set_hook`),
+/// with the interpreter in the scope the statement runs in. Returning an error stops the
+/// program with it.
+pub trait ExecutionHook {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), RuntimeError>;
+}
+
+#[derive(Clone)]
+pub(crate) struct Hook(pub(crate) Rc<RefCell<dyn ExecutionHook>>);
+
+impl fmt::Debug for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hook")
+    }
+}
+
+const HELP: &str = "\
+break <line>   pause before each statement on <line>, or list breakpoints (b)
+delete <line>  remove the breakpoint on <line> (d)
+continue       run until the next breakpoint (c)
+step           run to the next statement, stepping into calls (s)
+next           run to the next statement, stepping over calls (n)
+out            run until the current function returns (o)
+env            print the variables in scope, innermost first (e)
+print <expr>   evaluate an expression in the paused scope (p)
+list           show the source around the paused line (l)
+quit           stop the program (q)";
+
+// where to pause next, besides breakpoints
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Resume {
+    Step,        // before the next statement
+    Next(usize), // before the next one at this call depth or shallower
+    Out(usize),  // before the next one shallower than this call depth
+    Continue,    // only at breakpoints
+}
+
+/// Pauses the program to take commands from `input`, starting before its first statement.
+/// Statements without a line of their own (blocks, `try`, a `while (true)`) never pause.
+pub struct Debugger<R, W> {
+    source: Vec<String>, // lines, for showing where the program paused
+    input: R,
+    output: W,
+    breakpoints: BTreeSet<usize>,
+    resume: Resume,
+    quit: bool,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(source: &str, input: R, output: W) -> Self {
+        Debugger {
+            source: source.lines().map(str::to_string).collect(),
+            input,
+            output,
+            breakpoints: BTreeSet::new(),
+            resume: Resume::Step,
+            quit: false,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+
+    /// Whether the program stopped because it was told to `quit` (or the input ran out).
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn say(&mut self, text: &str) -> Result<(), RuntimeError> {
+        writeln!(self.output, "{text}")
+            .and_then(|_| self.output.flush())
+            .map_err(|err| RuntimeError::Native(format!("Failed to write output: {err}.")))
+    }
+
+    fn source_line(&self, line: usize) -> &str {
+        self.source
+            .get(line.wrapping_sub(1))
+            .map_or("", |text| text.trim())
+    }
+
+    // takes commands until one of them resumes the program
+    fn pause(
+        &mut self,
+        interpreter: &mut Interpreter,
+        line: usize,
+        breakpoint: bool,
+    ) -> Result<(), RuntimeError> {
+        let label = if breakpoint {
+            "Breakpoint, line"
+        } else {
+            "line"
+        };
+        let text = format!("{label} {line}: {}", self.source_line(line));
+        self.say(&text)?;
+
+        loop {
+            write!(self.output, "(debug) ")
+                .and_then(|_| self.output.flush())
+                .map_err(|err| RuntimeError::Native(format!("Failed to write output: {err}.")))?;
+
+            let mut command = String::new();
+            let read = self
+                .input
+                .read_line(&mut command)
+                .map_err(|err| RuntimeError::Native(format!("Failed to read input: {err}.")))?;
+
+            let command = command.trim();
+            let (name, argument) = command
+                .split_once(char::is_whitespace)
+                .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+
+            let depth = interpreter.call_depth();
+            self.resume = match name {
+                _ if read == 0 => return self.stop(),
+                "q" | "quit" => return self.stop(),
+                "c" | "continue" => Resume::Continue,
+                "s" | "step" => Resume::Step,
+                "n" | "next" => Resume::Next(depth),
+                "o" | "out" => Resume::Out(depth),
+                "b" | "break" => {
+                    self.break_command(argument)?;
+                    continue;
+                }
+                "d" | "delete" => {
+                    self.delete_command(argument)?;
+                    continue;
+                }
+                "e" | "env" => {
+                    self.env_command(interpreter)?;
+                    continue;
+                }
+                "p" | "print" => {
+                    let result = match evaluate(interpreter, argument) {
+                        Ok(value) => describe(&value),
+                        Err(err) => err,
+                    };
+                    self.say(&result)?;
+                    continue;
+                }
+                "l" | "list" => {
+                    self.list_command(line)?;
+                    continue;
+                }
+                "h" | "help" => {
+                    self.say(HELP)?;
+                    continue;
+                }
+                "" => continue,
+                name => {
+                    let message = format!("Unknown command '{name}'. Type 'help' for a list.");
+                    self.say(&message)?;
+                    continue;
+                }
+            };
+
+            return Ok(());
+        }
+    }
+
+    fn stop(&mut self) -> Result<(), RuntimeError> {
+        self.quit = true;
+        Err(RuntimeError::Interrupted("Debugger quit.".to_string()))
+    }
+
+    fn break_command(&mut self, argument: &str) -> Result<(), RuntimeError> {
+        if argument.is_empty() {
+            let lines: Vec<String> = self.breakpoints.iter().map(usize::to_string).collect();
+            return match lines.is_empty() {
+                true => self.say("No breakpoints."),
+                false => self.say(&format!("Breakpoints on lines {}.", lines.join(", "))),
+            };
+        }
+
+        match argument.parse::<usize>() {
+            Ok(line) if line > 0 => {
+                self.breakpoints.insert(line);
+                let text = format!("Breakpoint on line {line}: {}", self.source_line(line));
+                self.say(&text)
+            }
+            _ => self.say(&format!("Expect a line number, got '{argument}'.")),
+        }
+    }
+
+    fn delete_command(&mut self, argument: &str) -> Result<(), RuntimeError> {
+        match argument.parse::<usize>() {
+            Ok(line) if self.breakpoints.remove(&line) => {
+                self.say(&format!("Deleted the breakpoint on line {line}."))
+            }
+            Ok(line) => self.say(&format!("No breakpoint on line {line}.")),
+            Err(_) => self.say(&format!("Expect a line number, got '{argument}'.")),
+        }
+    }
+
+    fn list_command(&mut self, line: usize) -> Result<(), RuntimeError> {
+        let first = line.saturating_sub(3).max(1);
+        let last = (line + 3).min(self.source.len());
+        let width = last.to_string().len();
+
+        let listing: Vec<String> = (first..=last)
+            .map(|number| {
+                let marker = if number == line { "->" } else { "  " };
+                format!("{marker} {number:>width$} | {}", self.source[number - 1])
+            })
+            .collect();
+        self.say(&listing.join("\n"))
+    }
+
+    // the runtime scope chain: locals by slot, then the file's globals by name
+    fn env_command(&mut self, interpreter: &Interpreter) -> Result<(), RuntimeError> {
+        let mut lines = Vec::new();
+        let mut environment = Some(interpreter.environment.clone());
+        let mut depth = 0;
+
+        while let Some(current) = environment {
+            let env = current.borrow();
+
+            // the builtins enclose every file's globals, and are the same for every program
+            if env.enclosing.is_none() {
+                break;
+            }
+
+            if env.is_global() {
+                lines.push("globals:".to_string());
+                let mut values: Vec<_> = env.values().iter().collect();
+                values.sort_by_key(|(name, _)| name.as_str());
+                lines.extend(
+                    values
+                        .into_iter()
+                        .map(|(name, value)| format!("  {name} = {}", describe(value))),
+                );
+            } else {
+                lines.push(format!("scope {depth}:"));
+                lines.extend(
+                    env.locals()
+                        .map(|(name, value)| format!("  {name} = {}", describe(value))),
+                );
+            }
+
+            depth += 1;
+            environment = env.enclosing.clone();
+        }
+
+        self.say(&lines.join("\n"))
+    }
+}
+
+impl<R: BufRead, W: Write> ExecutionHook for Debugger<R, W> {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), RuntimeError> {
+        let Some(line) = stmt.line() else {
+            return Ok(());
+        };
+
+        let depth = interpreter.call_depth();
+        let breakpoint = self.breakpoints.contains(&line);
+        let pause = breakpoint
+            || match self.resume {
+                Resume::Step => true,
+                Resume::Next(paused_depth) => depth <= paused_depth,
+                Resume::Out(paused_depth) => depth < paused_depth,
+                Resume::Continue => false,
+            };
+
+        if !pause {
+            return Ok(());
+        }
+
+        self.pause(interpreter, line, breakpoint)
+    }
+}
+
+// strings are quoted so they can't be mistaken for other values
+fn describe(value: &ExprValue) -> String {
+    match value {
+        ExprValue::String(s) => format!("\"{s}\""),
+        value => value.to_string(),
+    }
+}
+
+/// Evaluates `source` as an expression in the interpreter's current scope. Any side effects
+/// (assignments, calls) happen for real.
+pub fn evaluate(interpreter: &mut Interpreter, source: &str) -> Result<ExprValue, String> {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    if let Some(err) = errors.first() {
+        return Err(err.to_string());
+    }
+
+    let (statements, errors) = Parser::new(tokens).parse();
+    if let Some(err) = errors.first() {
+        return Err(err.to_string());
+    }
+
+    let [Stmt::Expression(expr, _)] = statements.as_slice() else {
+        return Err("Expect a single expression.".to_string());
+    };
+
+    // runtime environments line up with the resolver's scopes, so the paused chain can stand in
+    let scopes = scope_names(&interpreter.environment);
+
+    // its tokens come from a scan of their own, so they can't collide with the program's as keys
+    // of the resolved locals
+    Resolver::in_scopes(interpreter, scopes)
+        .resolve(&statements)
+        .map_err(|err| err.to_string())?;
+    interpreter.evaluate(expr).map_err(|err| err.to_string())
+}
+
+// names of the locals in every scope from the innermost up to the globals, outermost first
+fn scope_names(environment: &Rc<RefCell<Environment>>) -> Vec<Vec<String>> {
+    let mut scopes = Vec::new();
+    let mut environment = Some(environment.clone());
+
+    while let Some(current) = environment {
+        let env = current.borrow();
+        if env.is_global() {
+            break;
+        }
+
+        scopes.push(env.locals().map(|(name, _)| name.to_string()).collect());
+        environment = env.enclosing.clone();
+    }
+
+    scopes.reverse();
+    scopes
+}
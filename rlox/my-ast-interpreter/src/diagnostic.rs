@@ -0,0 +1,103 @@
+use crate::resolver::{BindingError, Warning};
+use crate::{parser::ParseError, scanner::ScanError, RuntimeError, Span};
+use std::fmt::Display;
+
+// errors that can point back at the token they were raised for
+pub trait Diagnostic: Display {
+    fn location(&self) -> Option<(usize, Span)>;
+}
+
+impl Diagnostic for ScanError {
+    fn location(&self) -> Option<(usize, Span)> {
+        Some((self.line, self.span))
+    }
+}
+
+impl Diagnostic for ParseError {
+    fn location(&self) -> Option<(usize, Span)> {
+        Some((self.line, self.span))
+    }
+}
+
+impl Diagnostic for BindingError {
+    fn location(&self) -> Option<(usize, Span)> {
+        Some((self.line, self.span))
+    }
+}
+
+impl Diagnostic for Warning {
+    fn location(&self) -> Option<(usize, Span)> {
+        Some((self.line, self.span))
+    }
+}
+
+impl Diagnostic for RuntimeError {
+    fn location(&self) -> Option<(usize, Span)> {
+        match self {
+            RuntimeError::RTE { line, span, .. } | RuntimeError::Throw { line, span, .. } => {
+                Some((*line, *span))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Renders an error followed by the source line it came from, underlining the offending token:
+///
+/// ```text
+/// [line 2] Runtime Error: Operands must be numbers
+///     2 | print "a" - 1;
+///       |           ^
+/// ```
+pub fn render(source: &str, error: &dyn Diagnostic) -> String {
+    render_source(None, source, error)
+}
+
+/// Like `render`, for an error raised in another file than the one being run (an imported
+/// module), naming that file above the source line:
+///
+/// ```text
+/// [line 2] Runtime Error: Operands must be numbers
+///   --> lib/math.lox:2:11
+///  2 | print "a" - 1;
+///    |           ^
+/// ```
+pub fn render_in_file(path: &str, source: &str, error: &dyn Diagnostic) -> String {
+    render_source(Some(path), source, error)
+}
+
+fn render_source(path: Option<&str>, source: &str, error: &dyn Diagnostic) -> String {
+    let Some((line, span)) = error.location() else {
+        return error.to_string();
+    };
+
+    let offset = span.offset.min(source.len());
+    let line_start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = source[offset..]
+        .find('\n')
+        .map_or(source.len(), |idx| offset + idx);
+
+    let text = source[line_start..line_end].trim_end_matches('\r');
+
+    // keep tabs so the carets line up with the source line as printed
+    let padding = source[line_start..offset]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+
+    // multi-line tokens (strings) are only underlined up to the end of their first line
+    let underline_end = (offset + span.len).min(line_end);
+    let width = source[offset..underline_end].chars().count().max(1);
+
+    let line_number = line.to_string();
+    let gutter = " ".repeat(line_number.len());
+
+    let file = path.map_or_else(String::new, |path| {
+        format!("\n {gutter}--> {path}:{line}:{}", span.column)
+    });
+
+    format!(
+        "{error}{file}\n {line_number} | {text}\n {gutter} | {padding}{}",
+        "^".repeat(width)
+    )
+}
@@ -0,0 +1,317 @@
+use crate::json::Json;
+use crate::{Callable, Expr, Stmt, Token, TokenLiteral};
+
+// Machine-readable dumps for editor tooling. Every AST node is an object tagged with its
+// "type"; tokens carry their position so tools can point back into the source.
+
+pub fn tokens(tokens: &[Token]) -> Json {
+    Json::Array(tokens.iter().map(token).collect())
+}
+
+pub fn token(token: &Token) -> Json {
+    let literal = match &token.literal {
+        Some(TokenLiteral::Number(n)) => Json::Number(*n),
+        Some(TokenLiteral::String(s)) => Json::string(s),
+        None => Json::Null,
+    };
+
+    Json::object([
+        ("type", Json::string(token.token_type.to_string())),
+        ("lexeme", Json::string(&token.lexeme)),
+        ("literal", literal),
+        ("line", token.line.into()),
+        ("column", token.span.column.into()),
+        ("offset", token.span.offset.into()),
+        ("length", token.span.len.into()),
+    ])
+}
+
+pub fn program(statements: &[Stmt]) -> Json {
+    Json::Array(statements.iter().map(stmt).collect())
+}
+
+pub fn stmt(stmt: &Stmt) -> Json {
+    match stmt {
+        Stmt::Expression(expression, _) => Json::object([
+            ("type", Json::string("Expression")),
+            ("expression", expr(expression)),
+        ]),
+        Stmt::Print(keyword, expression) => Json::object([
+            ("type", Json::string("Print")),
+            ("keyword", token(keyword)),
+            ("expression", expr(expression)),
+        ]),
+        Stmt::Var(name, initializer) => Json::object([
+            ("type", Json::string("Var")),
+            ("name", token(name)),
+            ("initializer", expr(initializer)),
+        ]),
+        Stmt::Block(statements, _) => Json::object([
+            ("type", Json::string("Block")),
+            ("statements", program(statements)),
+        ]),
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => Json::object([
+            ("type", Json::string("If")),
+            ("condition", expr(condition)),
+            ("thenBranch", self::stmt(then_branch)),
+            ("elseBranch", else_branch.as_deref().map(self::stmt).into()),
+        ]),
+        Stmt::While {
+            condition, body, ..
+        } => Json::object([
+            ("type", Json::string("While")),
+            ("condition", expr(condition)),
+            ("body", self::stmt(body)),
+        ]),
+        Stmt::For {
+            keyword,
+            initializer,
+            condition,
+            increment,
+            body,
+        } => Json::object([
+            ("type", Json::string("For")),
+            ("keyword", token(keyword)),
+            ("initializer", initializer.as_deref().map(self::stmt).into()),
+            ("condition", condition.as_ref().map(expr).into()),
+            ("increment", increment.as_ref().map(expr).into()),
+            ("body", self::stmt(body)),
+        ]),
+        Stmt::Function(function) => self::function(function),
+        Stmt::Class {
+            name,
+            superclass,
+            methods,
+            ..
+        } => Json::object([
+            ("type", Json::string("Class")),
+            ("name", token(name)),
+            ("superclass", superclass.as_ref().map(expr).into()),
+            (
+                "methods",
+                Json::Array(methods.iter().map(self::function).collect()),
+            ),
+        ]),
+        Stmt::Import {
+            keyword,
+            path,
+            name,
+        } => Json::object([
+            ("type", Json::string("Import")),
+            ("keyword", token(keyword)),
+            ("path", Json::string(path)),
+            ("name", name.as_ref().map(token).into()),
+        ]),
+        Stmt::Return(keyword, value) => Json::object([
+            ("type", Json::string("Return")),
+            ("keyword", token(keyword)),
+            ("value", value.as_ref().map(expr).into()),
+        ]),
+        Stmt::Throw(keyword, value) => Json::object([
+            ("type", Json::string("Throw")),
+            ("keyword", token(keyword)),
+            ("value", expr(value)),
+        ]),
+        Stmt::Try {
+            body,
+            catch,
+            finally,
+            ..
+        } => Json::object([
+            ("type", Json::string("Try")),
+            ("body", block(body)),
+            (
+                "catch",
+                catch
+                    .as_ref()
+                    .map(|(name, handler)| {
+                        Json::object([("name", token(name)), ("body", block(handler))])
+                    })
+                    .into(),
+            ),
+            ("finally", finally.as_deref().map(block).into()),
+        ]),
+        Stmt::Break(keyword) => {
+            Json::object([("type", Json::string("Break")), ("keyword", token(keyword))])
+        }
+        Stmt::Continue(keyword) => Json::object([
+            ("type", Json::string("Continue")),
+            ("keyword", token(keyword)),
+        ]),
+    }
+}
+
+// function declarations and methods
+// the statements of a `try` part, which is always parsed as a block
+fn block(stmt: &Stmt) -> Json {
+    match stmt {
+        Stmt::Block(statements, _) => program(statements),
+        stmt => self::stmt(stmt),
+    }
+}
+
+fn function(function: &Callable) -> Json {
+    let Callable::Function {
+        name, params, body, ..
+    } = function
+    else {
+        unreachable!("declarations only hold `Callable::Function`")
+    };
+
+    Json::object([
+        ("type", Json::string("Function")),
+        ("name", token(name)),
+        ("params", Json::Array(params.iter().map(token).collect())),
+        ("body", stmt(body)),
+    ])
+}
+
+pub fn expr(expr: &Expr) -> Json {
+    match expr {
+        Expr::Number(n) => literal(Json::Number(*n)),
+        Expr::String(s) => literal(Json::string(s)),
+        Expr::Bool(b) => literal(Json::Bool(*b)),
+        Expr::Nil => literal(Json::Null),
+        Expr::Grouping(expression) => Json::object([
+            ("type", Json::string("Grouping")),
+            ("expression", self::expr(expression)),
+        ]),
+        Expr::Unary { operator, right } => Json::object([
+            ("type", Json::string("Unary")),
+            ("operator", token(operator)),
+            ("right", self::expr(right)),
+        ]),
+        Expr::Binary {
+            operator,
+            left,
+            right,
+        } => Json::object([
+            ("type", Json::string("Binary")),
+            ("left", self::expr(left)),
+            ("operator", token(operator)),
+            ("right", self::expr(right)),
+        ]),
+        Expr::Logical {
+            operator,
+            left,
+            right,
+        } => Json::object([
+            ("type", Json::string("Logical")),
+            ("left", self::expr(left)),
+            ("operator", token(operator)),
+            ("right", self::expr(right)),
+        ]),
+        Expr::Variable(name) => {
+            Json::object([("type", Json::string("Variable")), ("name", token(name))])
+        }
+        Expr::Assign(name, value) => Json::object([
+            ("type", Json::string("Assign")),
+            ("name", token(name)),
+            ("value", self::expr(value)),
+        ]),
+        Expr::Call {
+            callee,
+            paren,
+            arguments,
+        } => Json::object([
+            ("type", Json::string("Call")),
+            ("callee", self::expr(callee)),
+            ("paren", token(paren)),
+            (
+                "arguments",
+                Json::Array(arguments.iter().map(|arg| self::expr(arg)).collect()),
+            ),
+        ]),
+        Expr::Get { object, name } => Json::object([
+            ("type", Json::string("Get")),
+            ("object", self::expr(object)),
+            ("name", token(name)),
+        ]),
+        Expr::Set {
+            object,
+            name,
+            value,
+        } => Json::object([
+            ("type", Json::string("Set")),
+            ("object", self::expr(object)),
+            ("name", token(name)),
+            ("value", self::expr(value)),
+        ]),
+        Expr::This(keyword) => {
+            Json::object([("type", Json::string("This")), ("keyword", token(keyword))])
+        }
+        Expr::Super { keyword, method } => Json::object([
+            ("type", Json::string("Super")),
+            ("keyword", token(keyword)),
+            ("method", token(method)),
+        ]),
+        Expr::Function {
+            keyword,
+            params,
+            body,
+        } => Json::object([
+            ("type", Json::string("Lambda")),
+            ("keyword", token(keyword)),
+            ("params", Json::Array(params.iter().map(token).collect())),
+            ("body", stmt(body)),
+        ]),
+        Expr::Interpolation(parts) => Json::object([
+            ("type", Json::string("Interpolation")),
+            ("parts", Json::Array(parts.iter().map(self::expr).collect())),
+        ]),
+        Expr::List { bracket, elements } => Json::object([
+            ("type", Json::string("List")),
+            ("bracket", token(bracket)),
+            (
+                "elements",
+                Json::Array(elements.iter().map(self::expr).collect()),
+            ),
+        ]),
+        Expr::Map { brace, entries } => Json::object([
+            ("type", Json::string("Map")),
+            ("brace", token(brace)),
+            (
+                "entries",
+                Json::Array(
+                    entries
+                        .iter()
+                        .map(|(key, value)| {
+                            Json::object([("key", self::expr(key)), ("value", self::expr(value))])
+                        })
+                        .collect(),
+                ),
+            ),
+        ]),
+        Expr::Index {
+            object,
+            bracket,
+            index,
+        } => Json::object([
+            ("type", Json::string("Index")),
+            ("object", self::expr(object)),
+            ("bracket", token(bracket)),
+            ("index", self::expr(index)),
+        ]),
+        Expr::SetIndex {
+            object,
+            bracket,
+            index,
+            value,
+        } => Json::object([
+            ("type", Json::string("SetIndex")),
+            ("object", self::expr(object)),
+            ("bracket", token(bracket)),
+            ("index", self::expr(index)),
+            ("value", self::expr(value)),
+        ]),
+    }
+}
+
+fn literal(value: Json) -> Json {
+    Json::object([("type", Json::string("Literal")), ("value", value)])
+}
@@ -1,9 +1,25 @@
-use crate::{ExprValue, RuntimeError, Token};
+use crate::{gc, ExprValue, RuntimeError, Token};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+// where the resolver found a local: `depth` environments out, at index `slot` in that one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Local {
+    pub depth: usize,
+    pub slot: usize,
+}
+
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Environment {
+    // globals can be declared after code using them is resolved, so they're looked up by name;
+    // locals are defined in the order the resolver declared them, so a slot index finds them
     values: HashMap<String, ExprValue>,
+    slots: Vec<ExprValue>,
+    names: Vec<String>, // of each slot, only kept when `named` (for the debugger)
+    named: bool,        // inherited by enclosed environments
+    global: bool,
+    // files imported without a namespace: their globals are looked up (and assigned) in place,
+    // after this scope's own
+    imports: Vec<Rc<RefCell<Environment>>>,
     pub enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -11,24 +27,140 @@ impl Environment {
     pub fn new() -> Self {
         Environment {
             values: HashMap::new(),
+            slots: Vec::new(),
+            names: Vec::new(),
+            named: false,
+            global: true,
+            imports: Vec::new(),
             enclosing: None,
         }
     }
 
+    // a file's top-level scope, enclosed only by the builtins
+    pub fn global(enclosing: Rc<RefCell<Environment>>) -> Self {
+        let named = enclosing.borrow().named;
+        Environment {
+            named,
+            enclosing: Some(enclosing),
+            ..Environment::new()
+        }
+    }
+
     pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        let named = enclosing.borrow().named;
         Environment {
             values: HashMap::new(),
+            slots: Vec::new(),
+            names: Vec::new(),
+            named,
+            global: false,
+            imports: Vec::new(),
             enclosing: Some(enclosing),
         }
     }
 
-    pub fn define(&mut self, name: String, value: ExprValue) {
-        self.values.insert(name, value);
+    // keeps the names of locals defined from now on, in this scope and the ones it encloses
+    // later; off by default since it costs an allocation per local
+    pub fn name_locals(&mut self) {
+        self.named = true;
+    }
+
+    // shares the environment, registering it with the cycle collector
+    pub fn into_shared(self) -> Rc<RefCell<Environment>> {
+        gc::track(self)
+    }
+
+    pub fn define(&mut self, name: &str, value: ExprValue) {
+        if self.global {
+            self.values.insert(name.to_string(), value);
+        } else {
+            self.slots.push(value);
+            if self.named {
+                self.names.push(name.to_string());
+            }
+        }
+    }
+
+    // bindings declared directly in this scope by name (globals), not its enclosing ones
+    pub fn values(&self) -> &HashMap<String, ExprValue> {
+        &self.values
+    }
+
+    // makes a module's globals visible here without copying them, so later changes to them show;
+    // `Err` with the first name that would then mean two different variables
+    pub fn import(&mut self, module: &Rc<RefCell<Environment>>) -> Result<(), String> {
+        if self.imports.iter().any(|import| Rc::ptr_eq(import, module)) {
+            return Ok(());
+        }
+
+        let mut names = Vec::new();
+        module.borrow().exported_names(&mut names);
+        names.sort();
+
+        for name in names {
+            let existing = self.import_defining(&name);
+            let clashes = self.values.contains_key(&name)
+                || existing.is_some_and(|existing| {
+                    !Rc::ptr_eq(
+                        &existing,
+                        &defining(module, &name).expect("should export name"),
+                    )
+                });
+            if clashes {
+                return Err(name);
+            }
+        }
+
+        self.imports.push(module.clone());
+        Ok(())
+    }
+
+    // globals, including the ones imported without a namespace
+    pub fn exported(&self, name: &str) -> Option<ExprValue> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .import_defining(name)
+                .map(|import| import.borrow().values[name].clone()),
+        }
+    }
+
+    fn exported_names(&self, names: &mut Vec<String>) {
+        names.extend(self.values.keys().cloned());
+        for import in &self.imports {
+            import.borrow().exported_names(names);
+        }
+    }
+
+    fn import_defining(&self, name: &str) -> Option<Rc<RefCell<Environment>>> {
+        self.imports
+            .iter()
+            .find_map(|import| defining(import, name))
+    }
+
+    pub(crate) fn imports(&self) -> &[Rc<RefCell<Environment>>] {
+        &self.imports
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.global
+    }
+
+    // locals declared directly in this scope in slot order, named `?` unless `name_locals` was on
+    pub fn locals(&self) -> impl Iterator<Item = (&str, &ExprValue)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .map(|(slot, value)| (self.names.get(slot).map_or("?", String::as_str), value))
+    }
+
+    pub(crate) fn references(&self) -> impl Iterator<Item = &ExprValue> {
+        self.values.values().chain(self.slots.iter())
     }
 
     pub fn get(&self, name: &Token) -> Result<ExprValue, RuntimeError> {
-        if let Some(val) = self.values.get(&name.lexeme) {
-            Ok(val.clone())
+        if let Some(val) = self.exported(&name.lexeme) {
+            Ok(val)
         } else if let Some(enclosing) = &self.enclosing {
             enclosing.borrow().get(name)
         } else {
@@ -36,23 +168,33 @@ impl Environment {
                 token: name.lexeme.clone(),
                 message: format!("Undefined variable '{}'.", name.lexeme),
                 line: name.line,
+                span: name.span,
             })
         }
     }
 
-    pub fn get_at(&self, distance: usize, name: &Token) -> ExprValue {
-        self.ancestor(distance)
-            .borrow()
-            .values
-            .get(&name.lexeme)
-            .expect("should find a valid variable expression value")
-            .clone()
+    pub fn get_at(&self, local: Local) -> ExprValue {
+        if local.depth == 0 {
+            return self.slot(local.slot).clone();
+        }
+
+        self.ancestor(local.depth).borrow().slot(local.slot).clone()
     }
 
-    fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
-        let mut environment = Rc::new(RefCell::new(self.clone()));
+    fn slot(&self, slot: usize) -> &ExprValue {
+        self.slots
+            .get(slot)
+            .expect("should find a value in the resolved slot")
+    }
 
-        for _ in 0..distance {
+    // only called with `depth` > 0: the shared chain is walked, nothing gets copied
+    fn ancestor(&self, depth: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = self
+            .enclosing
+            .clone()
+            .expect("should find enclosing environment");
+
+        for _ in 1..depth {
             let enclosing = environment.borrow().enclosing.clone();
             environment = enclosing.expect("should find enclosing environment");
         }
@@ -64,6 +206,12 @@ impl Environment {
         if self.values.contains_key(&name.lexeme) {
             self.values.insert(name.lexeme.clone(), value);
             Ok(())
+        } else if let Some(import) = self.import_defining(&name.lexeme) {
+            import
+                .borrow_mut()
+                .values
+                .insert(name.lexeme.clone(), value);
+            Ok(())
         } else if let Some(enclosing) = &mut self.enclosing {
             enclosing.borrow_mut().assign(name, value)
         } else {
@@ -71,14 +219,32 @@ impl Environment {
                 token: name.lexeme.clone(),
                 message: format!("Undefined variable '{}'", name.lexeme),
                 line: name.line,
+                span: name.span,
             })
         }
     }
 
-    pub fn assign_at(&self, distance: usize, name: &Token, value: ExprValue) {
-        self.ancestor(distance)
-            .borrow_mut()
-            .values
-            .insert(name.lexeme.clone(), value);
+    pub fn assign_at(&mut self, local: Local, value: ExprValue) {
+        if local.depth == 0 {
+            *self.slot_mut(local.slot) = value;
+            return;
+        }
+
+        *self.ancestor(local.depth).borrow_mut().slot_mut(local.slot) = value;
+    }
+
+    fn slot_mut(&mut self, slot: usize) -> &mut ExprValue {
+        self.slots
+            .get_mut(slot)
+            .expect("should find a value in the resolved slot")
+    }
+}
+
+// the module's own globals first, then those it imported without a namespace
+fn defining(module: &Rc<RefCell<Environment>>, name: &str) -> Option<Rc<RefCell<Environment>>> {
+    if module.borrow().values.contains_key(name) {
+        Some(module.clone())
+    } else {
+        module.borrow().import_defining(name)
     }
 }
@@ -1,4 +1,4 @@
-use crate::Token;
+use crate::{Stmt, Token};
 use std::{
     fmt::{Display, Formatter},
     hash::{Hash, Hasher},
@@ -32,6 +32,77 @@ pub enum Expr {
         paren: Token, // for location/RTE info re: fn's closing paren
         arguments: Vec<Box<Expr>>,
     },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    This(Token),
+    Super {
+        keyword: Token,
+        method: Token,
+    },
+    Function {
+        keyword: Token, // 'fun', stands in for the missing name token
+        params: Vec<Token>,
+        body: Box<Stmt>,
+    },
+    // literal segments are `Expr::String`s, everything else came from a `${...}`
+    Interpolation(Vec<Expr>),
+    List {
+        bracket: Token, // opening '[' for location/RTE info
+        elements: Vec<Expr>,
+    },
+    Map {
+        brace: Token, // opening '{'
+        entries: Vec<(Expr, Expr)>,
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token, // closing ']'
+        index: Box<Expr>,
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+}
+
+impl Expr {
+    /// Line of the leftmost token the expression kept, `None` for a lone literal.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Nil => None,
+            Expr::Grouping(expr) => expr.line(),
+            Expr::Unary { operator, .. } => Some(operator.line),
+            Expr::Binary { operator, left, .. } | Expr::Logical { operator, left, .. } => {
+                left.line().or(Some(operator.line))
+            }
+            Expr::Variable(name) | Expr::Assign(name, _) => Some(name.line),
+            Expr::Call { callee, paren, .. } => callee.line().or(Some(paren.line)),
+            Expr::Get { object, name } | Expr::Set { object, name, .. } => {
+                object.line().or(Some(name.line))
+            }
+            Expr::This(keyword) | Expr::Super { keyword, .. } | Expr::Function { keyword, .. } => {
+                Some(keyword.line)
+            }
+            Expr::Interpolation(parts) => parts.iter().find_map(Expr::line),
+            Expr::List { bracket, .. } => Some(bracket.line),
+            Expr::Map { brace, .. } => Some(brace.line),
+            Expr::Index {
+                object, bracket, ..
+            }
+            | Expr::SetIndex {
+                object, bracket, ..
+            } => object.line().or(Some(bracket.line)),
+        }
+    }
 }
 
 impl Display for Expr {
@@ -68,6 +139,65 @@ impl Display for Expr {
                 }
                 write!(f, ")")
             }
+            Expr::Get { object, name } => write!(f, "{}.{}", object, name.lexeme),
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => write!(f, "{}.{} = {}", object, name.lexeme, value),
+            Expr::This(_) => write!(f, "this"),
+            Expr::Super { method, .. } => write!(f, "super.{}", method.lexeme),
+            Expr::Function { params, body, .. } => {
+                write!(f, "fun (")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i < params.len() - 1 {
+                        write!(f, "{}, ", param)?;
+                    } else {
+                        write!(f, "{}", param)?;
+                    }
+                }
+                writeln!(f, ") {{")?;
+                write!(f, "{}", body)?;
+                write!(f, "}}")
+            }
+            Expr::Interpolation(parts) => {
+                for part in parts {
+                    match part {
+                        Expr::String(s) => write!(f, "{s}")?,
+                        expr => write!(f, "${{{expr}}}")?,
+                    }
+                }
+                Ok(())
+            }
+            Expr::List { elements, .. } => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i < elements.len() - 1 {
+                        write!(f, "{}, ", element)?;
+                    } else {
+                        write!(f, "{}", element)?;
+                    }
+                }
+                write!(f, "]")
+            }
+            Expr::Map { entries, .. } => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i < entries.len() - 1 {
+                        write!(f, "{}: {}, ", key, value)?;
+                    } else {
+                        write!(f, "{}: {}", key, value)?;
+                    }
+                }
+                write!(f, "}}")
+            }
+            Expr::Index { object, index, .. } => write!(f, "{}[{}]", object, index),
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => write!(f, "{}[{}] = {}", object, index, value),
         }
     }
 }
@@ -129,6 +259,98 @@ impl PartialEq for Expr {
                     arguments: a_b,
                 },
             ) => c_a == c_b && p_a == p_b && a_a == a_b,
+            (
+                Expr::Get {
+                    object: o_a,
+                    name: n_a,
+                },
+                Expr::Get {
+                    object: o_b,
+                    name: n_b,
+                },
+            ) => o_a == o_b && n_a == n_b,
+            (
+                Expr::Set {
+                    object: o_a,
+                    name: n_a,
+                    value: v_a,
+                },
+                Expr::Set {
+                    object: o_b,
+                    name: n_b,
+                    value: v_b,
+                },
+            ) => o_a == o_b && n_a == n_b && v_a == v_b,
+            (Expr::This(a), Expr::This(b)) => a == b,
+            (
+                Expr::Super {
+                    keyword: k_a,
+                    method: m_a,
+                },
+                Expr::Super {
+                    keyword: k_b,
+                    method: m_b,
+                },
+            ) => k_a == k_b && m_a == m_b,
+            (
+                Expr::Function {
+                    keyword: k_a,
+                    params: p_a,
+                    body: b_a,
+                },
+                Expr::Function {
+                    keyword: k_b,
+                    params: p_b,
+                    body: b_b,
+                },
+            ) => k_a == k_b && p_a == p_b && b_a == b_b,
+            (Expr::Interpolation(a), Expr::Interpolation(b)) => a == b,
+            (
+                Expr::List {
+                    bracket: b_a,
+                    elements: e_a,
+                },
+                Expr::List {
+                    bracket: b_b,
+                    elements: e_b,
+                },
+            ) => b_a == b_b && e_a == e_b,
+            (
+                Expr::Map {
+                    brace: b_a,
+                    entries: e_a,
+                },
+                Expr::Map {
+                    brace: b_b,
+                    entries: e_b,
+                },
+            ) => b_a == b_b && e_a == e_b,
+            (
+                Expr::Index {
+                    object: o_a,
+                    bracket: b_a,
+                    index: i_a,
+                },
+                Expr::Index {
+                    object: o_b,
+                    bracket: b_b,
+                    index: i_b,
+                },
+            ) => o_a == o_b && b_a == b_b && i_a == i_b,
+            (
+                Expr::SetIndex {
+                    object: o_a,
+                    bracket: b_a,
+                    index: i_a,
+                    value: v_a,
+                },
+                Expr::SetIndex {
+                    object: o_b,
+                    bracket: b_b,
+                    index: i_b,
+                    value: v_b,
+                },
+            ) => o_a == o_b && b_a == b_b && i_a == i_b && v_a == v_b,
             _ => false,
         }
     }
@@ -180,6 +402,60 @@ impl Hash for Expr {
                 paren.hash(state);
                 arguments.hash(state);
             }
+            Expr::Get { object, name } => {
+                object.hash(state);
+                name.hash(state);
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                object.hash(state);
+                name.hash(state);
+                value.hash(state);
+            }
+            Expr::This(t) => t.hash(state),
+            Expr::Super { keyword, method } => {
+                keyword.hash(state);
+                method.hash(state);
+            }
+            // the keyword's span already makes each anonymous function's key unique
+            Expr::Function {
+                keyword, params, ..
+            } => {
+                keyword.hash(state);
+                params.hash(state);
+            }
+            Expr::Interpolation(parts) => parts.hash(state),
+            Expr::List { bracket, elements } => {
+                bracket.hash(state);
+                elements.hash(state);
+            }
+            Expr::Map { brace, entries } => {
+                brace.hash(state);
+                entries.hash(state);
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                object.hash(state);
+                bracket.hash(state);
+                index.hash(state);
+            }
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                object.hash(state);
+                bracket.hash(state);
+                index.hash(state);
+                value.hash(state);
+            }
         }
     }
 }
@@ -0,0 +1,431 @@
+use crate::{Callable, Comment, Expr, Stmt};
+
+const INDENT: &str = "  ";
+
+/// Prints a parsed program back out as canonical Lox: two-space indentation, one statement per
+/// line and a blank line around function and class declarations. Parsing the result gives the
+/// same program back.
+pub fn format(statements: &[Stmt]) -> String {
+    format_with_comments(statements, &[])
+}
+
+/// Like `format`, putting the program's comments (from `Scanner::scan_with_comments`) back in.
+/// A comment stays at the end of the statement it followed, or goes on its own line before the
+/// next statement or closing brace; one inside an expression that spans lines moves out to the
+/// next statement.
+pub fn format_with_comments(statements: &[Stmt], comments: &[Comment]) -> String {
+    let mut formatter = Formatter {
+        comments,
+        ..Formatter::default()
+    };
+    formatter.statements(statements);
+    formatter.comments_before(usize::MAX);
+    formatter.out
+}
+
+#[derive(Default)]
+struct Formatter<'a> {
+    out: String,
+    depth: usize,
+    comments: &'a [Comment], // not yet written
+}
+
+impl Formatter<'_> {
+    fn statements(&mut self, statements: &[Stmt]) {
+        for (idx, stmt) in statements.iter().enumerate() {
+            if idx > 0 && (is_declaration(&statements[idx - 1]) || is_declaration(stmt)) {
+                self.out.push('\n');
+            }
+
+            let line = stmt.line();
+            if let Some(line) = line {
+                self.comments_before(line);
+            }
+
+            self.indent();
+            self.stmt(stmt);
+            if let Some(line) = line {
+                self.trailing_comment(line);
+            }
+            self.out.push('\n');
+        }
+    }
+
+    // each on a line of its own, at the start of one
+    fn comments_before(&mut self, line: usize) {
+        let comments = self.comments;
+        let count = comments.iter().take_while(|c| c.line < line).count();
+
+        for comment in &comments[..count] {
+            self.indent();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+        }
+        self.comments = &comments[count..];
+    }
+
+    fn has_comments_before(&self, line: usize) -> bool {
+        self.comments.first().is_some_and(|c| c.line < line)
+    }
+
+    // one that followed the code on `line`, before the end of the line
+    fn trailing_comment(&mut self, line: usize) {
+        if let Some((comment, rest)) = self.comments.split_first() {
+            if comment.trailing && comment.line == line {
+                self.out.push(' ');
+                self.out.push_str(&comment.text);
+                self.comments = rest;
+            }
+        }
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    // comments before the closing brace on `end` stay inside the block
+    fn block(&mut self, statements: &[Stmt], end: usize) {
+        if statements.is_empty() && !self.has_comments_before(end) {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push_str("{\n");
+        self.depth += 1;
+        self.statements(statements);
+        self.comments_before(end);
+        self.depth -= 1;
+        self.indent();
+        self.out.push('}');
+    }
+
+    // function bodies are always parsed as blocks
+    fn body(&mut self, body: &Stmt) {
+        match body {
+            Stmt::Block(statements, right_brace) => self.block(statements, right_brace.line),
+            stmt => self.block(std::slice::from_ref(stmt), 0),
+        }
+    }
+
+    // writes a statement from the current position, without indenting it or ending the line
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr, _) => {
+                self.expr(expr);
+                self.out.push(';');
+            }
+            Stmt::Print(_, expr) => {
+                self.out.push_str("print ");
+                self.expr(expr);
+                self.out.push(';');
+            }
+            Stmt::Var(name, initializer) => {
+                self.out.push_str("var ");
+                self.out.push_str(&name.lexeme);
+                if initializer != &Expr::Nil {
+                    self.out.push_str(" = ");
+                    self.expr(initializer);
+                }
+                self.out.push(';');
+            }
+            Stmt::Block(statements, right_brace) => self.block(statements, right_brace.line),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.out.push_str("if (");
+                self.expr(condition);
+                self.out.push_str(") ");
+                self.stmt(then_branch);
+
+                // kept on the same line, so a dangling `else` reads as binding to the nearest `if`
+                if let Some(else_branch) = else_branch {
+                    self.out.push_str(" else ");
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::While {
+                condition, body, ..
+            } => {
+                self.out.push_str("while (");
+                self.expr(condition);
+                self.out.push_str(") ");
+                self.stmt(body);
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                self.out.push_str("for (");
+                match initializer {
+                    Some(initializer) => self.stmt(initializer),
+                    None => self.out.push(';'),
+                }
+                if let Some(condition) = condition {
+                    self.out.push(' ');
+                    self.expr(condition);
+                }
+                self.out.push(';');
+                if let Some(increment) = increment {
+                    self.out.push(' ');
+                    self.expr(increment);
+                }
+                self.out.push_str(") ");
+                self.stmt(body);
+            }
+            Stmt::Function(function) => {
+                self.out.push_str("fun ");
+                self.function(function);
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                right_brace,
+            } => {
+                self.out.push_str("class ");
+                self.out.push_str(&name.lexeme);
+                if let Some(superclass) = superclass {
+                    self.out.push_str(" < ");
+                    self.expr(superclass);
+                }
+
+                if methods.is_empty() && !self.has_comments_before(right_brace.line) {
+                    self.out.push_str(" {}");
+                    return;
+                }
+
+                self.out.push_str(" {\n");
+                self.depth += 1;
+                for (idx, method) in methods.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.push('\n');
+                    }
+
+                    if let Callable::Function { name, .. } = method {
+                        self.comments_before(name.line);
+                    }
+                    self.indent();
+                    self.function(method);
+                    self.out.push('\n');
+                }
+                self.comments_before(right_brace.line);
+                self.depth -= 1;
+                self.indent();
+                self.out.push('}');
+            }
+            Stmt::Import { path, name, .. } => {
+                self.out.push_str(&format!("import \"{path}\""));
+                if let Some(name) = name {
+                    self.out.push_str(" as ");
+                    self.out.push_str(&name.lexeme);
+                }
+                self.out.push(';');
+            }
+            Stmt::Return(_, value) => {
+                self.out.push_str("return");
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.expr(value);
+                }
+                self.out.push(';');
+            }
+            Stmt::Throw(_, value) => {
+                self.out.push_str("throw ");
+                self.expr(value);
+                self.out.push(';');
+            }
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.out.push_str("try ");
+                self.stmt(body);
+
+                if let Some((name, handler)) = catch {
+                    self.out.push_str(&format!(" catch ({}) ", name.lexeme));
+                    self.stmt(handler);
+                }
+
+                if let Some(finally) = finally {
+                    self.out.push_str(" finally ");
+                    self.stmt(finally);
+                }
+            }
+            Stmt::Break(_) => self.out.push_str("break;"),
+            Stmt::Continue(_) => self.out.push_str("continue;"),
+        }
+    }
+
+    // a named function or method, from its name onwards
+    fn function(&mut self, function: &Callable) {
+        let Callable::Function {
+            name, params, body, ..
+        } = function
+        else {
+            unreachable!("declarations only hold `Callable::Function`")
+        };
+
+        self.out.push_str(&name.lexeme);
+        self.params(params.iter().map(|param| param.lexeme.as_str()));
+        self.out.push(' ');
+        self.body(body);
+    }
+
+    fn params<'a>(&mut self, params: impl Iterator<Item = &'a str>) {
+        self.out.push('(');
+        self.out.push_str(&params.collect::<Vec<&str>>().join(", "));
+        self.out.push(')');
+    }
+
+    fn list<'a>(&mut self, exprs: impl Iterator<Item = &'a Expr>) {
+        for (idx, expr) in exprs.enumerate() {
+            if idx > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Bool(b) => self.out.push_str(&b.to_string()),
+            Expr::Number(n) => self.out.push_str(&n.to_string()),
+            Expr::String(s) => self.out.push_str(&format!("\"{s}\"")),
+            Expr::Nil => self.out.push_str("nil"),
+            Expr::Grouping(expr) => {
+                self.out.push('(');
+                self.expr(expr);
+                self.out.push(')');
+            }
+            Expr::Unary { operator, right } => {
+                self.out.push_str(&operator.lexeme);
+                self.expr(right);
+            }
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            }
+            | Expr::Logical {
+                operator,
+                left,
+                right,
+            } => {
+                self.expr(left);
+                self.out.push_str(&format!(" {} ", operator.lexeme));
+                self.expr(right);
+            }
+            Expr::Variable(name) => self.out.push_str(&name.lexeme),
+            Expr::Assign(name, value) => {
+                self.out.push_str(&name.lexeme);
+                self.out.push_str(" = ");
+                self.expr(value);
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee);
+                self.out.push('(');
+                self.list(arguments.iter().map(|arg| arg.as_ref()));
+                self.out.push(')');
+            }
+            Expr::Get { object, name } => {
+                self.expr(object);
+                self.out.push('.');
+                self.out.push_str(&name.lexeme);
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                self.expr(object);
+                self.out.push('.');
+                self.out.push_str(&name.lexeme);
+                self.out.push_str(" = ");
+                self.expr(value);
+            }
+            Expr::This(_) => self.out.push_str("this"),
+            Expr::Super { method, .. } => {
+                self.out.push_str("super.");
+                self.out.push_str(&method.lexeme);
+            }
+            Expr::Function { params, body, .. } => {
+                self.out.push_str("fun ");
+                self.params(params.iter().map(|param| param.lexeme.as_str()));
+                self.out.push(' ');
+                self.body(body);
+            }
+            Expr::Interpolation(parts) => {
+                // literal text and `${}` holes alternate, except that empty text isn't kept
+                let mut literal_next = true;
+
+                self.out.push('"');
+                for part in parts {
+                    match part {
+                        Expr::String(s) if literal_next => {
+                            self.out.push_str(s);
+                            literal_next = false;
+                        }
+                        expr => {
+                            self.out.push_str("${");
+                            self.expr(expr);
+                            self.out.push('}');
+                            literal_next = true;
+                        }
+                    }
+                }
+                self.out.push('"');
+            }
+            Expr::List { elements, .. } => {
+                self.out.push('[');
+                self.list(elements.iter());
+                self.out.push(']');
+            }
+            Expr::Map { entries, .. } => {
+                self.out.push('{');
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(key);
+                    self.out.push_str(": ");
+                    self.expr(value);
+                }
+                self.out.push('}');
+            }
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.out.push('[');
+                self.expr(index);
+                self.out.push(']');
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.out.push('[');
+                self.expr(index);
+                self.out.push_str("] = ");
+                self.expr(value);
+            }
+        }
+    }
+}
+
+fn is_declaration(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Function(_) | Stmt::Class { .. })
+}
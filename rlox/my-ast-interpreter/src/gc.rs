@@ -0,0 +1,312 @@
+use crate::{Callable, Class, Environment, ExprValue, Instance, Map, Module};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+// Closures capture their environment and usually get stored back into it, so plain reference
+// counting leaks them. Every shared environment is registered here; collecting traces the heap
+// reachable from them and counts how many references each object gets from inside that heap.
+// Whatever holds more references than that is also referenced from outside (the interpreter,
+// the Rust stack, a host) and is kept along with everything it reaches. The rest can only be
+// reached through cycles, which are broken by emptying it.
+
+// how many environments get registered before the first collection
+const INITIAL_THRESHOLD: usize = 1024;
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const {
+        RefCell::new(Heap {
+            environments: Vec::new(),
+            threshold: INITIAL_THRESHOLD,
+        })
+    };
+}
+
+struct Heap {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    threshold: usize,
+}
+
+impl Heap {
+    fn prune(&mut self) -> usize {
+        self.environments.retain(|env| env.strong_count() > 0);
+        self.environments.len()
+    }
+}
+
+pub(crate) fn track(environment: Environment) -> Rc<RefCell<Environment>> {
+    let environment = Rc::new(RefCell::new(environment));
+
+    let full = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.environments.push(Rc::downgrade(&environment));
+
+        if heap.environments.len() < heap.threshold {
+            return false;
+        }
+
+        // most registered environments are usually dead already: only collect if pruning them
+        // doesn't make enough room
+        let live = heap.prune();
+        if live * 2 > heap.threshold {
+            return true;
+        }
+
+        heap.threshold = INITIAL_THRESHOLD.max(live * 2);
+        false
+    });
+
+    if full {
+        collect();
+    }
+
+    environment
+}
+
+/// Environments still alive on this thread, whichever interpreter created them.
+pub fn live_environments() -> usize {
+    HEAP.with(|heap| heap.borrow_mut().prune())
+}
+
+/// Frees environments (and the values they hold) that are only kept alive by reference cycles.
+/// Returns how many environments were freed.
+pub fn collect() -> usize {
+    let environments: Vec<_> = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.prune();
+        heap.environments.iter().filter_map(Weak::upgrade).collect()
+    });
+
+    let mut graph = Graph::default();
+    for environment in environments {
+        graph.add(Object::Environment(environment));
+    }
+
+    // `nodes` grows as tracing finds more objects
+    let mut idx = 0;
+    while idx < graph.nodes.len() {
+        graph.trace(idx);
+        idx += 1;
+    }
+
+    let garbage = graph.garbage();
+    let freed = garbage
+        .iter()
+        .filter(|&&idx| matches!(graph.nodes[idx].object, Object::Environment(_)))
+        .count();
+
+    for idx in garbage {
+        graph.nodes[idx].object.clear();
+    }
+
+    // dropping the graph releases its own references, the last ones left to the garbage
+    drop(graph);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        let live = heap.prune();
+        heap.threshold = INITIAL_THRESHOLD.max(live * 2);
+    });
+
+    freed
+}
+
+// everything reference-counted that can (directly or not) hold an environment
+enum Object {
+    Environment(Rc<RefCell<Environment>>),
+    Instance(Rc<RefCell<Instance>>),
+    List(Rc<RefCell<Vec<ExprValue>>>),
+    Map(Rc<RefCell<Map>>),
+    Class(Rc<Class>),
+    Module(Rc<Module>),
+}
+
+impl Object {
+    fn address(&self) -> usize {
+        match self {
+            Object::Environment(env) => Rc::as_ptr(env) as *const () as usize,
+            Object::Instance(instance) => Rc::as_ptr(instance) as *const () as usize,
+            Object::List(list) => Rc::as_ptr(list) as *const () as usize,
+            Object::Map(map) => Rc::as_ptr(map) as *const () as usize,
+            Object::Class(class) => Rc::as_ptr(class) as *const () as usize,
+            Object::Module(module) => Rc::as_ptr(module) as *const () as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Object::Environment(env) => Rc::strong_count(env),
+            Object::Instance(instance) => Rc::strong_count(instance),
+            Object::List(list) => Rc::strong_count(list),
+            Object::Map(map) => Rc::strong_count(map),
+            Object::Class(class) => Rc::strong_count(class),
+            Object::Module(module) => Rc::strong_count(module),
+        }
+    }
+
+    // `None` when the object is borrowed right now, so its contents can't be looked at
+    fn children(&self) -> Option<Vec<Object>> {
+        let mut children = Vec::new();
+
+        match self {
+            Object::Environment(env) => {
+                let env = env.try_borrow().ok()?;
+                env.references()
+                    .for_each(|value| value_children(value, &mut children));
+                children.extend(env.imports().iter().cloned().map(Object::Environment));
+                if let Some(enclosing) = &env.enclosing {
+                    children.push(Object::Environment(enclosing.clone()));
+                }
+            }
+            Object::Instance(instance) => {
+                let instance = instance.try_borrow().ok()?;
+                children.push(Object::Class(instance.class.clone()));
+                instance
+                    .fields()
+                    .for_each(|value| value_children(value, &mut children));
+            }
+            Object::List(list) => list
+                .try_borrow()
+                .ok()?
+                .iter()
+                .for_each(|value| value_children(value, &mut children)),
+            Object::Map(map) => map
+                .try_borrow()
+                .ok()?
+                .iter()
+                .for_each(|(_, value)| value_children(value, &mut children)),
+            Object::Class(class) => {
+                if let Some(superclass) = &class.superclass {
+                    children.push(Object::Class(superclass.clone()));
+                }
+                class
+                    .methods()
+                    .for_each(|method| callable_children(method, &mut children));
+            }
+            Object::Module(module) => {
+                children.push(Object::Environment(module.environment.clone()));
+            }
+        }
+
+        Some(children)
+    }
+
+    // classes and modules can't be emptied, but every cycle through them also runs through
+    // one of the mutable objects that can
+    fn clear(&self) {
+        match self {
+            Object::Environment(env) => {
+                if let Ok(mut env) = env.try_borrow_mut() {
+                    *env = Environment::default();
+                }
+            }
+            Object::Instance(instance) => {
+                if let Ok(mut instance) = instance.try_borrow_mut() {
+                    instance.clear_fields();
+                }
+            }
+            Object::List(list) => {
+                if let Ok(mut list) = list.try_borrow_mut() {
+                    list.clear();
+                }
+            }
+            Object::Map(map) => {
+                if let Ok(mut map) = map.try_borrow_mut() {
+                    *map = Map::new();
+                }
+            }
+            Object::Class(_) | Object::Module(_) => (),
+        }
+    }
+}
+
+fn value_children(value: &ExprValue, children: &mut Vec<Object>) {
+    match value {
+        ExprValue::Call(callable) => callable_children(callable, children),
+        ExprValue::Instance(instance) => children.push(Object::Instance(instance.clone())),
+        ExprValue::List(list) => children.push(Object::List(list.clone())),
+        ExprValue::Map(map) => children.push(Object::Map(map.clone())),
+        ExprValue::Module(module) => children.push(Object::Module(module.clone())),
+        ExprValue::Bool(_) | ExprValue::Number(_) | ExprValue::String(_) | ExprValue::Nil => (),
+    }
+}
+
+// natives are opaque: anything they captured counts as referenced from outside
+fn callable_children(callable: &Callable, children: &mut Vec<Object>) {
+    match callable {
+        Callable::Function { closure, .. } => children.push(Object::Environment(closure.clone())),
+        Callable::Class(class) => children.push(Object::Class(class.clone())),
+        Callable::NativeFn { .. } => (),
+    }
+}
+
+struct Node {
+    object: Object,
+    edges: Vec<usize>,
+    internal: usize, // references held by other objects in the graph
+    pinned: bool,    // couldn't be traced, so it has to be kept
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    index: HashMap<usize, usize>, // object address to its node
+}
+
+impl Graph {
+    fn add(&mut self, object: Object) -> usize {
+        let address = object.address();
+        if let Some(&idx) = self.index.get(&address) {
+            return idx;
+        }
+
+        self.nodes.push(Node {
+            object,
+            edges: Vec::new(),
+            internal: 0,
+            pinned: false,
+        });
+        self.index.insert(address, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn trace(&mut self, idx: usize) {
+        let Some(children) = self.nodes[idx].object.children() else {
+            self.nodes[idx].pinned = true;
+            return;
+        };
+
+        for child in children {
+            let child = self.add(child);
+            self.nodes[child].internal += 1;
+            self.nodes[idx].edges.push(child);
+        }
+    }
+
+    fn garbage(&self) -> Vec<usize> {
+        let mut reachable = vec![false; self.nodes.len()];
+
+        // the graph itself holds one reference to every object
+        let mut stack: Vec<usize> = (0..self.nodes.len())
+            .filter(|&idx| {
+                let node = &self.nodes[idx];
+                node.pinned || node.object.strong_count() > node.internal + 1
+            })
+            .collect();
+
+        while let Some(idx) = stack.pop() {
+            if reachable[idx] {
+                continue;
+            }
+
+            reachable[idx] = true;
+            stack.extend(self.nodes[idx].edges.iter().copied());
+        }
+
+        (0..self.nodes.len())
+            .filter(|&idx| !reachable[idx])
+            .collect()
+    }
+}
@@ -1,10 +1,18 @@
-use crate::Callable;
-use crate::{Environment, Expr, Stmt, Token, TokenType};
+use crate::debugger::{ExecutionHook, Hook};
+use crate::diagnostic::{render, render_in_file, Diagnostic};
+use crate::{natives, Callable, Class, Instance, Map, MapKey, Module, NativeFunction};
+use crate::{Environment, Expr, Local, Span, Stmt, Token, TokenType};
+use crate::{Parser, Resolver, Scanner, Sink};
 use std::{
     cell::RefCell,
     collections::HashMap,
     fmt::{Display, Formatter},
+    path::{Path, PathBuf},
     rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -14,8 +22,30 @@ pub enum RuntimeError {
         token: String,
         message: String,
         line: usize,
+        span: Span,
+    },
+    // raised by native fns, which don't know where they were called from (see `evaluate_call`)
+    #[error("Runtime Error: {0}")]
+    Native(String),
+    // a Lox value raised by `throw`, unwinding until a `catch` (or the top level) handles it
+    #[error("[line {line}] Runtime Error: {message}")]
+    Throw {
+        value: Box<ExprValue>,
+        message: String,
+        line: usize,
+        span: Span,
     },
-    #[error("return value of {0:?}")]
+    // a step budget ran out or the host cancelled the run; `catch` can't intercept this
+    #[error("Runtime Error: {0}")]
+    Interrupted(String),
+}
+
+// how a statement finished: non-local exits unwind through `execute` until a loop or call handles them
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlFlow {
+    Normal,
+    Break,
+    Continue,
     Return(ExprValue),
 }
 
@@ -26,6 +56,39 @@ pub enum ExprValue {
     String(String),
     Nil,
     Call(Callable),
+    Instance(Rc<RefCell<Instance>>),
+    // shared and mutable: copies of a list value alias the same elements
+    List(Rc<RefCell<Vec<ExprValue>>>),
+    Map(Rc<RefCell<Map>>),
+    Module(Rc<Module>),
+}
+
+thread_local! {
+    // lists and maps part-way through being displayed, by address
+    static DISPLAYING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+// runs `display` unless the list or map at `value` is already being displayed further up, so
+// one that contains itself can't recurse forever; `None` means it was
+pub(crate) fn display_once(
+    value: *const (),
+    display: impl FnOnce() -> std::fmt::Result,
+) -> Option<std::fmt::Result> {
+    let nested = DISPLAYING.with_borrow_mut(|displaying| {
+        let nested = displaying.contains(&value);
+        if !nested {
+            displaying.push(value);
+        }
+        nested
+    });
+
+    if nested {
+        return None;
+    }
+
+    let result = display();
+    DISPLAYING.with_borrow_mut(|displaying| displaying.pop());
+    Some(result)
 }
 
 impl Display for ExprValue {
@@ -38,6 +101,24 @@ impl Display for ExprValue {
             ExprValue::String(s) => write!(f, "{s}"),
             ExprValue::Nil => write!(f, "nil"),
             ExprValue::Call(callable) => write!(f, "{}", callable),
+            ExprValue::Instance(instance) => write!(f, "{}", instance.borrow()),
+            ExprValue::List(list) => {
+                let displayed = display_once(Rc::as_ptr(list).cast(), || {
+                    let elements = list.borrow();
+                    write!(f, "[")?;
+                    for (i, element) in elements.iter().enumerate() {
+                        if i < elements.len() - 1 {
+                            write!(f, "{}, ", element)?;
+                        } else {
+                            write!(f, "{}", element)?;
+                        }
+                    }
+                    write!(f, "]")
+                });
+                displayed.unwrap_or_else(|| write!(f, "[...]"))
+            }
+            ExprValue::Map(map) => write!(f, "{}", map.borrow()),
+            ExprValue::Module(module) => write!(f, "{}", module),
         }
     }
 }
@@ -47,6 +128,7 @@ enum InterpreterStatus {
     #[default]
     Evaluate,
     Run,
+    Repl,
 }
 
 impl TryFrom<&str> for InterpreterStatus {
@@ -55,54 +137,268 @@ impl TryFrom<&str> for InterpreterStatus {
         match value {
             "evaluate" => Ok(InterpreterStatus::Evaluate),
             "run" => Ok(InterpreterStatus::Run),
-            _ => Err("should only accept `evaluate` and `run` string values".to_string()),
+            "repl" => Ok(InterpreterStatus::Repl),
+            _ => Err("should only accept `evaluate`, `run` and `repl` string values".to_string()),
         }
     }
 }
 
-#[derive(Default, Debug, Clone)]
+/// What the I/O natives (`readLine`, `readFile`, `writeFile`, ...) are allowed to do.
+/// Use `ReadOnly` or `Deny` when running scripts you don't trust.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IoPolicy {
+    #[default]
+    Allow,
+    ReadOnly,
+    Deny,
+}
+
+impl TryFrom<&str> for IoPolicy {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "all" => Ok(IoPolicy::Allow),
+            "read-only" => Ok(IoPolicy::ReadOnly),
+            "none" => Ok(IoPolicy::Deny),
+            _ => Err("should only accept `all`, `read-only` and `none` string values".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct Interpreter {
     pub environment: Rc<RefCell<Environment>>,
     status: InterpreterStatus,
-    // globals: Environment,
+    builtins: Rc<RefCell<Environment>>, // natives, enclosing every file's global scope
     globals: Rc<RefCell<Environment>>,
-    pub locals: HashMap<Expr, usize>,
+    pub locals: HashMap<Expr, Local>,
+    script: Option<PathBuf>, // file currently executing, imports resolve relative to it
+    modules: HashMap<PathBuf, Rc<Module>>,
+    module_sources: HashMap<usize, (String, Rc<str>)>, // import path and text, by `Span::source`
+    sources: HashMap<usize, Rc<str>>,                  // text the host scanned, by `Span::source`
+    importing: Vec<PathBuf>, // files part-way through their first import, for cycle detection
+    error_class: Rc<Class>,  // class of the values `catch` binds
+    io_policy: IoPolicy,
+    output: Sink,      // `print` and echoed expression results
+    diagnostics: Sink, // messages about the program rather than from it
+    max_steps: Option<usize>,
+    steps: usize, // statements executed by the current `interpret` call
+    max_call_depth: usize,
+    call_depth: usize,
+    cancelled: Arc<AtomicBool>,
+    hook: Option<Hook>, // called before each statement, e.g. by the debugger
+}
+
+/// Default for `Interpreter::set_max_call_depth`. Calls get more native stack when the thread's
+/// runs low, so any depth fits whatever thread the interpreter runs on.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+// a Lox call takes around 20 KiB of native stack in a debug build, more when its expressions
+// nest deeply; with less than this left, the next call runs on a fresh segment
+const CALL_STACK_RED_ZONE: usize = 256 * 1024;
+const CALL_STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        // TODO: need to organize globals for the `clock` function implemented in 'jlox'
-        let globals = Rc::new(RefCell::new(Environment::new()));
-        Interpreter {
+        let builtins = Environment::new().into_shared();
+        let globals = Environment::global(builtins.clone()).into_shared();
+        let mut interpreter = Interpreter {
             environment: globals.clone(),
             status: InterpreterStatus::Evaluate,
+            builtins,
             globals,
             locals: HashMap::new(),
-        }
+            script: None,
+            modules: HashMap::new(),
+            module_sources: HashMap::new(),
+            sources: HashMap::new(),
+            importing: Vec::new(),
+            error_class: Rc::new(Class::new("Error".to_string(), None, HashMap::new())),
+            io_policy: IoPolicy::default(),
+            output: Sink::stdout(),
+            diagnostics: Sink::stderr(),
+            max_steps: None,
+            steps: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            call_depth: 0,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            hook: None,
+        };
+
+        natives::define_core(&mut interpreter);
+        interpreter
     }
 
+    /// Registers a host function as a global callable from Lox scripts.
+    /// Errors without a location should use `RuntimeError::Native`, the call site's line gets attached.
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, func: F)
+    where
+        F: Fn(&mut Interpreter, &[ExprValue]) -> Result<ExprValue, RuntimeError> + 'static,
+    {
+        let native = Callable::NativeFn {
+            name: name.to_string(),
+            arity,
+            func: NativeFunction::new(func),
+        };
+
+        self.builtins
+            .borrow_mut()
+            .define(name, ExprValue::Call(native));
+    }
+
+    /// Sets the file being run, so `import` paths in it resolve relative to its directory.
+    pub fn set_script_path(&mut self, path: impl Into<PathBuf>) {
+        self.script = Some(path.into());
+    }
+
+    /// Runs a resolved program. An error no `catch` handled is also written to the diagnostics
+    /// sink, unless it's the host stopping the run (a step limit, cancellation, the debugger).
     pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
-        for statement in statements.iter() {
-            self.execute(statement)?;
+        self.steps = 0;
+
+        let result = statements.iter().try_for_each(|statement| {
+            match statement {
+                // repl only echoes top-level expression results, not ones nested in blocks or calls
+                Stmt::Expression(expr, _) if self.status == InterpreterStatus::Repl => {
+                    let val = self.evaluate(expr)?;
+                    self.output.emit(&val.to_string())?;
+                }
+                _ => {
+                    self.execute(statement)?;
+                }
+            }
+
+            Ok(())
+        });
+
+        if let Err(err) = &result {
+            if !matches!(err, RuntimeError::Interrupted(_)) {
+                self.report(err);
+            }
         }
 
-        Ok(())
+        result
     }
 
-    pub fn resolve(&mut self, expr: &Expr, depth: usize) {
-        self.locals.insert(expr.clone(), depth);
+    pub fn resolve(&mut self, expr: &Expr, local: Local) {
+        self.locals.insert(expr.clone(), local);
     }
 
-    fn look_up_var(&mut self, token: &Token, expr: &Expr) -> ExprValue {
-        if let Some(distance) = self.locals.get(expr) {
-            self.environment.borrow().get_at(*distance, token)
+    fn look_up_var(&mut self, token: &Token, expr: &Expr) -> Result<ExprValue, RuntimeError> {
+        if let Some(local) = self.locals.get(expr) {
+            Ok(self.environment.borrow().get_at(*local))
         } else {
-            self.globals
-                .borrow()
-                .get(token)
-                .expect("globals should have defined var from variable expression")
+            self.global_scope().borrow().get(token)
+        }
+    }
+
+    // unresolved names are globals of whichever file the running code was declared in:
+    // the outermost environment before the shared builtins
+    fn global_scope(&self) -> Rc<RefCell<Environment>> {
+        let mut environment = self.environment.clone();
+
+        loop {
+            let enclosing = environment.borrow().enclosing.clone();
+            match enclosing {
+                Some(enclosing) if !Rc::ptr_eq(&enclosing, &self.builtins) => {
+                    environment = enclosing
+                }
+                _ => return environment,
+            }
+        }
+    }
+
+    /// The import path and text of the module `span` points into, so errors raised in imported
+    /// code can be rendered against the right file. `None` for code the host scanned itself.
+    pub fn module_source(&self, span: Span) -> Option<(&str, &str)> {
+        self.module_sources
+            .get(&span.source)
+            .map(|(path, source)| (path.as_str(), &**source))
+    }
+
+    /// Keeps the text the host scanned as `source_id` (see `Scanner::source_id`), so diagnostics
+    /// about it show the line they point at.
+    pub fn add_source(&mut self, source_id: usize, source: &str) {
+        self.sources.insert(source_id, source.into());
+    }
+
+    /// Writes `diagnostic` to the diagnostics sink, rendered against the source it points into
+    /// when that's an imported module or was added with `add_source`.
+    pub fn report(&self, diagnostic: &dyn Diagnostic) {
+        let span = diagnostic.location().map(|(_, span)| span);
+
+        let text = match span {
+            Some(span) => match (self.module_source(span), self.sources.get(&span.source)) {
+                (Some((path, source)), _) => render_in_file(path, source, diagnostic),
+                (None, Some(source)) => render(source, diagnostic),
+                (None, None) => diagnostic.to_string(),
+            },
+            None => diagnostic.to_string(),
+        };
+
+        // a broken diagnostics sink has nowhere left to report to
+        let _ = self.diagnostics.write_line(&text);
+    }
+
+    /// Redirects program output, e.g. into a buffer when embedding or testing.
+    pub fn set_output(&mut self, sink: Sink) {
+        self.output = sink;
+    }
+
+    pub fn set_diagnostics(&mut self, sink: Sink) {
+        self.diagnostics = sink;
+    }
+
+    pub fn diagnostics(&self) -> &Sink {
+        &self.diagnostics
+    }
+
+    pub fn set_io_policy(&mut self, policy: IoPolicy) {
+        self.io_policy = policy;
+    }
+
+    pub fn io_policy(&self) -> IoPolicy {
+        self.io_policy
+    }
+
+    /// Caps how many statements one `interpret` call may execute, `None` for no limit.
+    pub fn set_max_steps(&mut self, max_steps: Option<usize>) {
+        self.max_steps = max_steps;
+    }
+
+    /// Calls nested deeper than this fail with a catchable "Stack overflow." runtime error.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Flag another thread can set to stop the running program before its next statement.
+    /// It stays set until stored back to `false`.
+    pub fn cancellation_flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    /// Runs `hook` before every statement from now on, `None` to stop. Local variables defined
+    /// while a hook is set keep their names, so it can show them.
+    pub fn set_hook(&mut self, hook: Option<Rc<RefCell<dyn ExecutionHook>>>) {
+        if hook.is_some() {
+            for environment in [&self.builtins, &self.globals, &self.environment] {
+                environment.borrow_mut().name_locals();
+            }
         }
+
+        self.hook = hook.map(Hook);
+    }
+
+    /// How many Lox calls are in progress, 0 at the top level.
+    pub fn call_depth(&self) -> usize {
+        self.call_depth
     }
 
     pub fn set_status(&mut self, status: &str) -> Result<(), String> {
@@ -112,20 +408,83 @@ impl Interpreter {
         Ok(())
     }
 
-    fn execute(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+    fn execute(&mut self, stmt: &Stmt) -> Result<ControlFlow, RuntimeError> {
+        self.check_limits()?;
+
+        // taken out while it runs, so statements it has evaluated don't call it again
+        if let Some(hook) = self.hook.take() {
+            let result = hook.0.borrow_mut().before_statement(self, stmt);
+            self.hook = Some(hook);
+            result?;
+        }
+
         match stmt {
-            Stmt::Expression(_) => self.eval_expr_stmt(stmt),
-            Stmt::Print(_) => self.eval_print_stmt(stmt),
-            Stmt::Var(name, initializer) => self.eval_var_stmt(name, initializer),
-            Stmt::Block(statements) => self.eval_block_stmt(statements, None),
+            Stmt::Expression(..) => self.eval_expr_stmt(stmt).map(|_| ControlFlow::Normal),
+            Stmt::Print(..) => self.eval_print_stmt(stmt).map(|_| ControlFlow::Normal),
+            Stmt::Var(name, initializer) => self
+                .eval_var_stmt(name, initializer)
+                .map(|_| ControlFlow::Normal),
+            Stmt::Block(statements, _) => self.eval_block_stmt(statements, None),
             Stmt::If {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => self.eval_if_stmt(condition, then_branch, else_branch),
-            Stmt::While { condition, body } => self.eval_while_stmt(condition, body),
-            Stmt::Function(callable) => self.eval_function_stmt(callable),
+            Stmt::While {
+                condition, body, ..
+            } => self.eval_while_stmt(Some(condition), body, &None),
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => self.eval_for_stmt(initializer, condition, increment, body),
+            Stmt::Function(callable) => self
+                .eval_function_stmt(callable)
+                .map(|_| ControlFlow::Normal),
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => self
+                .eval_class_stmt(name, superclass, methods)
+                .map(|_| ControlFlow::Normal),
             Stmt::Return(_, val) => self.eval_return_stmt(val),
+            Stmt::Import {
+                keyword,
+                path,
+                name,
+            } => self
+                .eval_import_stmt(keyword, path, name)
+                .map(|_| ControlFlow::Normal),
+            Stmt::Throw(keyword, value) => Err(self.eval_throw_stmt(keyword, value)?),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => self.eval_try_stmt(body, catch, finally),
+            Stmt::Break(_) => Ok(ControlFlow::Break),
+            Stmt::Continue(_) => Ok(ControlFlow::Continue),
+        }
+    }
+
+    fn check_limits(&mut self) -> Result<(), RuntimeError> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(RuntimeError::Interrupted(
+                "Execution cancelled.".to_string(),
+            ));
+        }
+
+        self.steps += 1;
+        match self.max_steps {
+            Some(max_steps) if self.steps > max_steps => Err(RuntimeError::Interrupted(
+                "Execution step limit exceeded.".to_string(),
+            )),
+            _ => Ok(()),
         }
     }
 
@@ -139,22 +498,314 @@ impl Interpreter {
                     params: params.clone(),
                     body: body.clone(),
                     closure: self.environment.clone(), // update to current interpreter env (re: parser sets to empty env)
+                    is_initializer: false,
                 };
                 let function_value = ExprValue::Call(function);
                 self.environment
                     .borrow_mut()
-                    .define(name.lexeme.clone(), function_value);
+                    .define(&name.lexeme, function_value);
             }
+            _ => unreachable!("function statements only hold `Callable::Function`"),
         }
 
         Ok(())
     }
 
+    fn eval_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[Callable],
+    ) -> Result<(), RuntimeError> {
+        let superclass = match superclass {
+            Some(superclass_expr) => match self.evaluate(superclass_expr)? {
+                ExprValue::Call(Callable::Class(class)) => Some(class),
+                _ => {
+                    let superclass_name = match superclass_expr {
+                        Expr::Variable(token) => token,
+                        _ => name,
+                    };
+
+                    return Err(RuntimeError::RTE {
+                        token: superclass_name.lexeme.clone(),
+                        message: "Superclass must be a class.".to_string(),
+                        line: superclass_name.line,
+                        span: superclass_name.span,
+                    });
+                }
+            },
+            None => None,
+        };
+
+        // methods of a subclass close over an extra env holding `super`
+        let prev_env = self.environment.clone();
+        if let Some(superclass) = &superclass {
+            let mut super_env = Environment::with_enclosing(prev_env.clone());
+            super_env.define(
+                "super",
+                ExprValue::Call(Callable::Class(superclass.clone())),
+            );
+            self.environment = super_env.into_shared();
+        }
+
+        let mut class_methods = HashMap::new();
+
+        for method in methods {
+            if let Callable::Function {
+                name, params, body, ..
+            } = method
+            {
+                let function = Callable::Function {
+                    name: name.clone(),
+                    params: params.clone(),
+                    body: body.clone(),
+                    closure: self.environment.clone(),
+                    is_initializer: name.lexeme == "init",
+                };
+                class_methods.insert(name.lexeme.clone(), function);
+            }
+        }
+
+        self.environment = prev_env;
+
+        // methods only look their class up once called, so it can be defined after they're built
+        let class = Class::new(name.lexeme.clone(), superclass, class_methods);
+        self.environment.borrow_mut().define(
+            &name.lexeme,
+            ExprValue::Call(Callable::Class(Rc::new(class))),
+        );
+
+        Ok(())
+    }
+
+    // evaluates to the error to unwind with (or a different one if evaluating `value` fails)
+    fn eval_throw_stmt(
+        &mut self,
+        keyword: &Token,
+        value: &Expr,
+    ) -> Result<RuntimeError, RuntimeError> {
+        let value = self.evaluate(value)?;
+
+        // rethrowing a caught error keeps its original message
+        let message = match &value {
+            ExprValue::Instance(instance) if self.is_error(instance) => instance
+                .borrow()
+                .field("message")
+                .map_or_else(String::new, |message| message.to_string()),
+            value => value.to_string(),
+        };
+
+        Ok(RuntimeError::Throw {
+            value: Box::new(value),
+            message,
+            line: keyword.line,
+            span: keyword.span,
+        })
+    }
+
+    fn eval_try_stmt(
+        &mut self,
+        body: &Stmt,
+        catch: &Option<(Token, Box<Stmt>)>,
+        finally: &Option<Box<Stmt>>,
+    ) -> Result<ControlFlow, RuntimeError> {
+        let result = match (self.execute(body), catch) {
+            (Err(err @ RuntimeError::Interrupted(_)), _) => return Err(err),
+            (Err(err), Some((name, handler))) => {
+                let Stmt::Block(handler, _) = handler.as_ref() else {
+                    unreachable!("`catch` handlers are parsed as blocks")
+                };
+                let mut catch_env = Environment::with_enclosing(self.environment.clone());
+                catch_env.define(&name.lexeme, self.error_value(err));
+                self.eval_block_stmt(handler, Some(catch_env))
+            }
+            (result, _) => result,
+        };
+
+        // `finally` runs however the try/catch exited, and only overrides that by exiting itself
+        if let Err(err @ RuntimeError::Interrupted(_)) = result {
+            return Err(err);
+        }
+
+        match finally {
+            Some(finally) => match self.execute(finally)? {
+                ControlFlow::Normal => result,
+                flow => Ok(flow),
+            },
+            None => result,
+        }
+    }
+
+    // what a `catch` binds: an `Error` instance with the message, line and originally thrown value
+    fn error_value(&self, err: RuntimeError) -> ExprValue {
+        let (value, message, line) = match err {
+            RuntimeError::Throw { value, line, .. } => match *value {
+                ExprValue::Instance(instance) if self.is_error(&instance) => {
+                    return ExprValue::Instance(instance);
+                }
+                value => (
+                    value.clone(),
+                    value.to_string(),
+                    ExprValue::Number(line as f64),
+                ),
+            },
+            RuntimeError::RTE { message, line, .. } => (
+                ExprValue::String(message.clone()),
+                message,
+                ExprValue::Number(line as f64),
+            ),
+            RuntimeError::Native(message) => {
+                (ExprValue::String(message.clone()), message, ExprValue::Nil)
+            }
+            RuntimeError::Interrupted(_) => unreachable!("`catch` should never see interruptions"),
+        };
+
+        let mut error = Instance::new(self.error_class.clone());
+        error.set_field("message", ExprValue::String(message));
+        error.set_field("line", line);
+        error.set_field("value", value);
+
+        ExprValue::Instance(Rc::new(RefCell::new(error)))
+    }
+
+    fn is_error(&self, instance: &Rc<RefCell<Instance>>) -> bool {
+        Rc::ptr_eq(&instance.borrow().class, &self.error_class)
+    }
+
+    fn eval_import_stmt(
+        &mut self,
+        keyword: &Token,
+        path: &str,
+        name: &Option<Token>,
+    ) -> Result<(), RuntimeError> {
+        let module = self.import(keyword, path)?;
+
+        match name {
+            Some(name) => self
+                .environment
+                .borrow_mut()
+                .define(&name.lexeme, ExprValue::Module(module)),
+            // no namespace: the module's declarations become globals of the importing file
+            None => {
+                let imported = self.environment.borrow_mut().import(&module.environment);
+                if let Err(name) = imported {
+                    return Err(RuntimeError::RTE {
+                        token: keyword.lexeme.clone(),
+                        message: format!(
+                            "Can't import '{name}' from '{path}': a global with that name already exists."
+                        ),
+                        line: keyword.line,
+                        span: keyword.span,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn import(&mut self, keyword: &Token, import_path: &str) -> Result<Rc<Module>, RuntimeError> {
+        let import_error = |message: String| RuntimeError::RTE {
+            token: keyword.lexeme.clone(),
+            message,
+            line: keyword.line,
+            span: keyword.span,
+        };
+
+        // reading other source files is filesystem access like any other
+        if self.io_policy == IoPolicy::Deny {
+            return Err(import_error(
+                "import is not permitted: I/O is disabled.".to_string(),
+            ));
+        }
+
+        let base = self
+            .script
+            .as_ref()
+            .and_then(|script| script.parent())
+            .unwrap_or(Path::new(""));
+        let path = base
+            .join(import_path)
+            .canonicalize()
+            .map_err(|err| import_error(format!("Can't open module '{import_path}': {err}.")))?;
+
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module.clone());
+        }
+
+        if let Some(start) = self.importing.iter().position(|p| p == &path) {
+            let cycle = self.importing[start..]
+                .iter()
+                .chain([&path])
+                .map(|p| p.file_name().unwrap_or_default().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            return Err(import_error(format!("Import cycle detected: {cycle}.")));
+        }
+
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| import_error(format!("Can't open module '{import_path}': {err}.")))?;
+
+        // compile errors in the module surface at the import, naming the file they came from
+        let in_module = |errors: &[&dyn std::fmt::Display]| {
+            let messages: Vec<String> = errors
+                .iter()
+                .map(|err| format!("In module '{import_path}': {err}"))
+                .collect();
+            import_error(messages.join("\n"))
+        };
+
+        let scanner = Scanner::new(source.clone());
+        self.module_sources.insert(
+            scanner.source_id(),
+            (import_path.to_string(), source.into()),
+        );
+
+        let (tokens, scan_errors) = scanner.scan_tokens();
+        if !scan_errors.is_empty() {
+            let errors: Vec<&dyn std::fmt::Display> =
+                scan_errors.iter().map(|err| err as _).collect();
+            return Err(in_module(&errors));
+        }
+
+        let (statements, parse_errors) = Parser::new(tokens).parse();
+        if !parse_errors.is_empty() {
+            let errors: Vec<&dyn std::fmt::Display> =
+                parse_errors.iter().map(|err| err as _).collect();
+            return Err(in_module(&errors));
+        }
+
+        Resolver::new(self)
+            .resolve(&statements)
+            .map_err(|err| in_module(&[&err]))?;
+
+        let environment = Environment::global(self.builtins.clone()).into_shared();
+
+        let prev_env = std::mem::replace(&mut self.environment, environment.clone());
+        let prev_script = self.script.replace(path.clone());
+        self.importing.push(path.clone());
+
+        let result = statements
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt).map(|_| ()));
+
+        self.importing.pop();
+        self.script = prev_script;
+        self.environment = prev_env;
+        result?;
+
+        let module = Rc::new(Module::new(path.clone(), environment));
+        self.modules.insert(path, module.clone());
+
+        Ok(module)
+    }
+
     pub fn eval_block_stmt(
         &mut self,
         statements: &[Stmt],
         environment: Option<Environment>,
-    ) -> Result<(), RuntimeError> {
+    ) -> Result<ControlFlow, RuntimeError> {
         let prev_env = self.environment.clone();
 
         let block_env = match environment {
@@ -162,14 +813,17 @@ impl Interpreter {
             None => Environment::with_enclosing(prev_env.clone()),
         };
 
-        self.environment = Rc::new(RefCell::new(block_env));
+        self.environment = block_env.into_shared();
 
-        let block_eval: Result<(), RuntimeError> = (|| {
+        let block_eval: Result<ControlFlow, RuntimeError> = (|| {
             for stmt in statements.iter() {
-                self.execute(stmt)?;
+                match self.execute(stmt)? {
+                    ControlFlow::Normal => (),
+                    flow => return Ok(flow),
+                }
             }
 
-            Ok(())
+            Ok(ControlFlow::Normal)
         })();
 
         self.environment = prev_env;
@@ -178,10 +832,10 @@ impl Interpreter {
 
     fn eval_expr_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
         match stmt {
-            Stmt::Expression(expr) => {
+            Stmt::Expression(expr, _) => {
                 let stmt = self.evaluate(expr)?;
                 if self.status == InterpreterStatus::Evaluate {
-                    println!("{}", stmt);
+                    self.output.emit(&stmt.to_string())?;
                 }
 
                 Ok(())
@@ -192,9 +846,9 @@ impl Interpreter {
 
     fn eval_print_stmt(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
         match stmt {
-            Stmt::Print(expr) => {
+            Stmt::Print(_, expr) => {
                 let stmt = self.evaluate(expr)?;
-                println!("{}", stmt);
+                self.output.emit(&stmt.to_string())?;
 
                 Ok(())
             }
@@ -202,32 +856,73 @@ impl Interpreter {
         }
     }
 
-    fn eval_return_stmt(&mut self, val: &Option<Expr>) -> Result<(), RuntimeError> {
-        match val {
-            Some(stmt_val) => {
-                let return_val = self.evaluate(stmt_val)?;
+    fn eval_return_stmt(&mut self, val: &Option<Expr>) -> Result<ControlFlow, RuntimeError> {
+        let return_val = match val {
+            Some(stmt_val) => self.evaluate(stmt_val)?,
+            None => ExprValue::Nil,
+        };
 
-                Err(RuntimeError::Return(return_val))
-            }
-            None => Ok(()),
-        }
+        Ok(ControlFlow::Return(return_val))
     }
 
     fn eval_var_stmt(&mut self, name: &Token, initializer: &Expr) -> Result<(), RuntimeError> {
         let expr = self.evaluate(initializer)?;
-        self.environment
-            .borrow_mut()
-            .define(name.lexeme.clone(), expr);
+        self.environment.borrow_mut().define(&name.lexeme, expr);
 
         Ok(())
     }
 
-    fn eval_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), RuntimeError> {
-        while self.is_truthy(&self.clone().evaluate(condition)?) {
-            self.execute(body)?;
+    // shared by `while` and `for`, a missing condition loops forever
+    fn eval_while_stmt(
+        &mut self,
+        condition: Option<&Expr>,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<ControlFlow, RuntimeError> {
+        loop {
+            if let Some(condition) = condition {
+                let condition = self.evaluate(condition)?;
+                if !self.is_truthy(&condition) {
+                    break;
+                }
+            }
+
+            match self.execute(body)? {
+                ControlFlow::Break => break,
+                ControlFlow::Normal | ControlFlow::Continue => (),
+                flow @ ControlFlow::Return(_) => return Ok(flow),
+            }
+
+            // a `for` increment still runs after a `continue`
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
         }
 
-        Ok(())
+        Ok(ControlFlow::Normal)
+    }
+
+    fn eval_for_stmt(
+        &mut self,
+        initializer: &Option<Box<Stmt>>,
+        condition: &Option<Expr>,
+        increment: &Option<Expr>,
+        body: &Stmt,
+    ) -> Result<ControlFlow, RuntimeError> {
+        let Some(initializer) = initializer else {
+            return self.eval_while_stmt(condition.as_ref(), body, increment);
+        };
+
+        // the initializer's variable is scoped to the loop
+        let prev_env = self.environment.clone();
+        self.environment = Environment::with_enclosing(prev_env.clone()).into_shared();
+
+        let loop_eval = self
+            .execute(initializer)
+            .and_then(|_| self.eval_while_stmt(condition.as_ref(), body, increment));
+
+        self.environment = prev_env;
+        loop_eval
     }
 
     fn eval_if_stmt(
@@ -235,18 +930,19 @@ impl Interpreter {
         condition: &Expr,
         then_branch: &Stmt,
         else_branch: &Option<Box<Stmt>>,
-    ) -> Result<(), RuntimeError> {
+    ) -> Result<ControlFlow, RuntimeError> {
         let expr_val = self.evaluate(condition)?;
 
         if self.is_truthy(&expr_val) {
-            self.execute(then_branch)?;
-        } else if else_branch.is_some() {
-            self.execute(else_branch.as_ref().expect("else_branch is some"))?;
+            self.execute(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(ControlFlow::Normal)
         }
-
-        Ok(())
     }
 
+    // arms stay one-liners: each Lox call recurses through here, so a big frame limits call depth
     pub fn evaluate(&mut self, expr: &Expr) -> Result<ExprValue, RuntimeError> {
         match expr {
             Expr::Bool(b) => Ok(ExprValue::Bool(*b)),
@@ -260,18 +956,8 @@ impl Interpreter {
                 right,
                 left,
             } => self.evaluate_binary(operator, left, right),
-            Expr::Variable(name) => Ok(self.look_up_var(name, expr)),
-            Expr::Assign(name, val) => {
-                let val = self.evaluate(val)?;
-                if let Some(distance) = self.locals.get(expr) {
-                    self.environment
-                        .borrow_mut()
-                        .assign_at(*distance, name, val.clone());
-                } else {
-                    self.globals.borrow_mut().assign(name, val.clone())?;
-                }
-                Ok(val)
-            }
+            Expr::Variable(name) => self.look_up_var(name, expr),
+            Expr::Assign(name, val) => self.evaluate_assign(expr, name, val),
             Expr::Logical {
                 operator,
                 left,
@@ -282,6 +968,199 @@ impl Interpreter {
                 paren,
                 arguments,
             } => self.evaluate_call(callee, paren, arguments),
+            Expr::Get { object, name } => self.evaluate_get(object, name),
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => self.evaluate_set(object, name, value),
+            Expr::This(keyword) => self.look_up_var(keyword, expr),
+            Expr::Super { method, .. } => self.evaluate_super(expr, method),
+            Expr::Function {
+                keyword,
+                params,
+                body,
+            } => Ok(self.evaluate_function(keyword, params, body)),
+            Expr::Interpolation(parts) => self.evaluate_interpolation(parts),
+            Expr::List { elements, .. } => self.evaluate_list(elements),
+            Expr::Map { brace, entries } => self.evaluate_map(brace, entries),
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => self.evaluate_index(object, bracket, index),
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => self.evaluate_set_index(object, bracket, index, value),
+        }
+    }
+
+    fn evaluate_assign(
+        &mut self,
+        expr: &Expr,
+        name: &Token,
+        val: &Expr,
+    ) -> Result<ExprValue, RuntimeError> {
+        let val = self.evaluate(val)?;
+        if let Some(local) = self.locals.get(expr) {
+            self.environment.borrow_mut().assign_at(*local, val.clone());
+        } else {
+            self.global_scope().borrow_mut().assign(name, val.clone())?;
+        }
+        Ok(val)
+    }
+
+    fn evaluate_get(&mut self, object: &Expr, name: &Token) -> Result<ExprValue, RuntimeError> {
+        match self.evaluate(object)? {
+            ExprValue::Instance(instance) => Instance::get(&instance, name),
+            ExprValue::Module(module) => module.get(name),
+            _ => Err(RuntimeError::RTE {
+                token: name.lexeme.clone(),
+                message: "Only instances have properties.".to_string(),
+                line: name.line,
+                span: name.span,
+            }),
+        }
+    }
+
+    fn evaluate_set(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<ExprValue, RuntimeError> {
+        match self.evaluate(object)? {
+            ExprValue::Instance(instance) => {
+                let value = self.evaluate(value)?;
+                instance.borrow_mut().set(name, value.clone());
+                Ok(value)
+            }
+            _ => Err(RuntimeError::RTE {
+                token: name.lexeme.clone(),
+                message: "Only instances have fields.".to_string(),
+                line: name.line,
+                span: name.span,
+            }),
+        }
+    }
+
+    fn evaluate_function(&self, keyword: &Token, params: &[Token], body: &Stmt) -> ExprValue {
+        ExprValue::Call(Callable::Function {
+            name: Token::new(
+                TokenType::IDENTIFIER,
+                "anonymous".to_string(),
+                None,
+                keyword.line,
+                keyword.span,
+            ),
+            params: params.to_vec(),
+            body: Box::new(body.clone()),
+            closure: self.environment.clone(),
+            is_initializer: false,
+        })
+    }
+
+    fn evaluate_interpolation(&mut self, parts: &[Expr]) -> Result<ExprValue, RuntimeError> {
+        let mut string = String::new();
+        for part in parts {
+            string.push_str(&self.evaluate(part)?.to_string());
+        }
+
+        Ok(ExprValue::String(string))
+    }
+
+    fn evaluate_list(&mut self, elements: &[Expr]) -> Result<ExprValue, RuntimeError> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+
+        Ok(ExprValue::List(Rc::new(RefCell::new(values))))
+    }
+
+    fn evaluate_map(
+        &mut self,
+        brace: &Token,
+        entries: &[(Expr, Expr)],
+    ) -> Result<ExprValue, RuntimeError> {
+        let mut map = Map::new();
+        for (key, value) in entries {
+            let key = self.evaluate(key)?;
+            let key = self.check_map_key(brace, &key)?;
+            let value = self.evaluate(value)?;
+            map.insert(key, value);
+        }
+
+        Ok(ExprValue::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn evaluate_index(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> Result<ExprValue, RuntimeError> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+
+        match object {
+            ExprValue::List(elements) => {
+                let idx = self.check_list_index(bracket, &index, elements.borrow().len())?;
+                Ok(elements.borrow()[idx].clone())
+            }
+            ExprValue::Map(map) => {
+                let key = self.check_map_key(bracket, &index)?;
+                let value = map.borrow().get(&key).cloned();
+
+                value.ok_or_else(|| RuntimeError::RTE {
+                    token: bracket.lexeme.clone(),
+                    message: format!("Undefined key '{}'.", key),
+                    line: bracket.line,
+                    span: bracket.span,
+                })
+            }
+            _ => Err(RuntimeError::RTE {
+                token: bracket.lexeme.clone(),
+                message: "Only lists and maps can be indexed.".to_string(),
+                line: bracket.line,
+                span: bracket.span,
+            }),
+        }
+    }
+
+    fn evaluate_set_index(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<ExprValue, RuntimeError> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+
+        match object {
+            ExprValue::List(elements) => {
+                // the value can change the list's length (`a[0] = pop(a)`), so check after it
+                let value = self.evaluate(value)?;
+                let idx = self.check_list_index(bracket, &index, elements.borrow().len())?;
+                elements.borrow_mut()[idx] = value.clone();
+                Ok(value)
+            }
+            ExprValue::Map(map) => {
+                let key = self.check_map_key(bracket, &index)?;
+                let value = self.evaluate(value)?;
+                map.borrow_mut().insert(key, value.clone());
+                Ok(value)
+            }
+            _ => Err(RuntimeError::RTE {
+                token: bracket.lexeme.clone(),
+                message: "Only lists and maps can be indexed.".to_string(),
+                line: bracket.line,
+                span: bracket.span,
+            }),
         }
     }
 
@@ -302,6 +1181,7 @@ impl Interpreter {
                 token: operator.to_string(),
                 message: "Invalid operator found in unary expression".to_string(),
                 line: operator.line,
+                span: operator.span,
             }),
         }
     }
@@ -348,6 +1228,7 @@ impl Interpreter {
                     token: operator.lexeme.to_string(),
                     message: "Operands must be two numbers or two strings.".to_string(),
                     line: operator.line,
+                    span: operator.span,
                 }),
             },
             TokenType::SLASH => {
@@ -364,6 +1245,7 @@ impl Interpreter {
                 token: operator.lexeme.to_string(),
                 message: "Unrecognized binary operator.".to_string(),
                 line: operator.line,
+                span: operator.span,
             }),
         }
     }
@@ -389,28 +1271,89 @@ impl Interpreter {
                     token: paren.lexeme.clone(),
                     message: format!("Expected {} arguments but got {}.", fn_arity, args_len),
                     line: paren.line,
+                    span: paren.span,
                 });
             }
 
-            Ok(function.call(self, args)?)
+            if self.call_depth >= self.max_call_depth {
+                return Err(RuntimeError::RTE {
+                    token: paren.lexeme.clone(),
+                    message: "Stack overflow.".to_string(),
+                    line: paren.line,
+                    span: paren.span,
+                });
+            }
+
+            self.call_depth += 1;
+            let result = stacker::maybe_grow(CALL_STACK_RED_ZONE, CALL_STACK_SEGMENT, || {
+                function.call(self, args)
+            });
+            self.call_depth -= 1;
+
+            result.map_err(|err| match err {
+                RuntimeError::Native(message) => RuntimeError::RTE {
+                    token: paren.lexeme.clone(),
+                    message,
+                    line: paren.line,
+                    span: paren.span,
+                },
+                err => err,
+            })
         } else {
             Err(RuntimeError::RTE {
                 token: paren.lexeme.clone(),
                 message: "Can only call functions and methods".to_string(),
                 line: paren.line,
+                span: paren.span,
             })
         }
     }
 
+    fn evaluate_super(&mut self, expr: &Expr, method: &Token) -> Result<ExprValue, RuntimeError> {
+        let local = *self
+            .locals
+            .get(expr)
+            .expect("resolver should have bound `super` to a local scope");
+
+        let superclass = match self.environment.borrow().get_at(local) {
+            ExprValue::Call(Callable::Class(class)) => class,
+            _ => unreachable!("`super` should always be bound to a class"),
+        };
+
+        // `this` is always bound one env inside of `super`
+        let this = Local {
+            depth: local.depth - 1,
+            slot: 0,
+        };
+        let instance = match self.environment.borrow().get_at(this) {
+            ExprValue::Instance(instance) => instance,
+            _ => unreachable!("`this` should always be bound to an instance"),
+        };
+
+        match superclass.find_method(&method.lexeme) {
+            Some(method) => Ok(ExprValue::Call(method.bind(instance))),
+            None => Err(RuntimeError::RTE {
+                token: method.lexeme.clone(),
+                message: format!("Undefined property '{}'.", method.lexeme),
+                line: method.line,
+                span: method.span,
+            }),
+        }
+    }
+
     fn evaluate_logical(
         &mut self,
         operator: &Token,
         left: &Expr,
         right: &Expr,
     ) -> Result<ExprValue, RuntimeError> {
+        let left = self.evaluate(left)?;
+
+        // short-circuit: return the left operand as-is when it alone decides the result
         match operator.token_type {
-            TokenType::OR => Ok(self.evaluate(left)?),
-            _ => Ok(self.evaluate(right)?),
+            TokenType::OR if self.is_truthy(&left) => Ok(left),
+            TokenType::AND if !self.is_truthy(&left) => Ok(left),
+            _ => self.evaluate(right),
         }
     }
 
@@ -426,6 +1369,7 @@ impl Interpreter {
                 token: operator.lexeme.to_string(),
                 message: "Operand must be a number.".to_string(),
                 line: operator.line,
+                span: operator.span,
             }),
         }
     }
@@ -442,11 +1386,42 @@ impl Interpreter {
                 token: operator.lexeme.to_string(),
                 message: "Operands must be numbers".to_string(),
                 line: operator.line,
+                span: operator.span,
             }),
         }
     }
 
-    fn is_truthy(&self, expr_val: &ExprValue) -> bool {
+    fn check_list_index(
+        &self,
+        bracket: &Token,
+        index: &ExprValue,
+        len: usize,
+    ) -> Result<usize, RuntimeError> {
+        let message = match index {
+            ExprValue::Number(n) if n.fract() != 0.0 => "List index must be an integer.",
+            ExprValue::Number(n) if *n < 0.0 || *n >= len as f64 => "List index out of bounds.",
+            ExprValue::Number(n) => return Ok(*n as usize),
+            _ => "List index must be a number.",
+        };
+
+        Err(RuntimeError::RTE {
+            token: bracket.lexeme.clone(),
+            message: message.to_string(),
+            line: bracket.line,
+            span: bracket.span,
+        })
+    }
+
+    fn check_map_key(&self, token: &Token, key: &ExprValue) -> Result<MapKey, RuntimeError> {
+        MapKey::new(key).ok_or_else(|| RuntimeError::RTE {
+            token: token.lexeme.clone(),
+            message: "Map keys must be strings, numbers, booleans or nil.".to_string(),
+            line: token.line,
+            span: token.span,
+        })
+    }
+
+    pub fn is_truthy(&self, expr_val: &ExprValue) -> bool {
         match expr_val {
             ExprValue::Nil => false,
             ExprValue::Bool(b) => *b,
@@ -458,8 +1433,13 @@ impl Interpreter {
         match (left, right) {
             (ExprValue::Nil, ExprValue::Nil) => true,
             (ExprValue::Bool(a), ExprValue::Bool(b)) => a == b,
-            (ExprValue::Number(a), ExprValue::Number(b)) => (a - b).abs() < f64::EPSILON,
+            (ExprValue::Number(a), ExprValue::Number(b)) => a == b,
             (ExprValue::String(a), ExprValue::String(b)) => a == b,
+            (ExprValue::Instance(a), ExprValue::Instance(b)) => Rc::ptr_eq(a, b),
+            (ExprValue::List(a), ExprValue::List(b)) => Rc::ptr_eq(a, b),
+            (ExprValue::Map(a), ExprValue::Map(b)) => Rc::ptr_eq(a, b),
+            (ExprValue::Module(a), ExprValue::Module(b)) => Rc::ptr_eq(a, b),
+            (ExprValue::Call(a), ExprValue::Call(b)) => a.is_same(b),
             _ => false,
         }
     }
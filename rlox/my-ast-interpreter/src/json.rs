@@ -0,0 +1,357 @@
+use std::fmt::{self, Display, Formatter};
+
+// just enough JSON for the machine-readable dumps and the language server's messages; objects
+// keep their keys in insertion order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(s: impl Into<String>) -> Json {
+        Json::String(s.into())
+    }
+
+    pub fn parse(text: &str) -> Result<Json, JsonError> {
+        let mut parser = JsonParser {
+            text: text.as_bytes(),
+            current: 0,
+        };
+
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.current < text.len() {
+            return Err(parser.error("Trailing characters after value."));
+        }
+
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+            .map(|n| n as usize)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid JSON at offset {offset}: {message}")]
+pub struct JsonError {
+    pub message: &'static str,
+    pub offset: usize,
+}
+
+struct JsonParser<'a> {
+    text: &'a [u8],
+    current: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &'static str) -> JsonError {
+        JsonError {
+            message,
+            offset: self.current,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.current).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.current += 1;
+        }
+    }
+
+    fn consume(&mut self, expected: u8, message: &'static str) -> Result<(), JsonError> {
+        self.whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(message));
+        }
+
+        self.current += 1;
+        Ok(())
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, JsonError> {
+        if !self.text[self.current..].starts_with(keyword.as_bytes()) {
+            return Err(self.error("Expect value."));
+        }
+
+        self.current += keyword.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[') => self.array(),
+            Some(b'{') => self.object(),
+            _ => Err(self.error("Expect value.")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.current;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.current += 1;
+        }
+
+        std::str::from_utf8(&self.text[start..self.current])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Json::Number)
+            .ok_or(JsonError {
+                message: "Invalid number.",
+                offset: start,
+            })
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.consume(b'"', "Expect string.")?;
+
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string.")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    self.current += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("Invalid escape.")),
+                    };
+                    self.current += 1;
+
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                }
+                Some(byte) => {
+                    bytes.push(byte);
+                    self.current += 1;
+                }
+            }
+        }
+        self.current += 1;
+
+        // only ever split at ASCII quotes and backslashes, so the input's UTF-8 stays intact
+        String::from_utf8(bytes).map_err(|_| self.error("Invalid UTF-8 in string."))
+    }
+
+    // `\uXXXX`, leaving `current` on its last digit; characters outside the BMP are written
+    // as a surrogate pair of two escapes
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or(self.error("Invalid unicode escape."));
+        }
+
+        if !self.text[self.current + 1..].starts_with(b"\\u") {
+            return Err(self.error("Unpaired surrogate."));
+        }
+        self.current += 2;
+
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("Unpaired surrogate."));
+        }
+
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            .ok_or(self.error("Invalid unicode escape."))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .text
+            .get(self.current + 1..self.current + 5)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or(self.error("Invalid unicode escape."))?;
+
+        self.current += 4;
+        Ok(digits)
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.consume(b'[', "Expect '['.")?;
+
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.current += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.current += 1,
+                Some(b']') => {
+                    self.current += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("Expect ',' or ']' after array element.")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.consume(b'{', "Expect '{'.")?;
+
+        let mut fields = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.current += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.consume(b':', "Expect ':' after object key.")?;
+            fields.push((key, self.value()?));
+
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.current += 1,
+                Some(b'}') => {
+                    self.current += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.error("Expect ',' or '}' after object field.")),
+            }
+        }
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            // JSON has no NaN or infinities
+            Json::Number(n) if !n.is_finite() => write!(f, "null"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
@@ -1,19 +1,39 @@
+pub mod ast_printer;
 pub mod callable;
+pub mod class;
+pub mod debugger;
+pub mod diagnostic;
+pub mod dump;
 pub mod environment;
 pub mod expr;
+pub mod formatter;
+pub mod gc;
 pub mod interpreter;
+pub mod json;
+pub mod lsp;
+pub mod map;
+pub mod module;
+pub mod natives;
 pub mod parser;
 pub mod resolver;
 pub mod scanner;
+pub mod sink;
 pub mod stmt;
 pub mod token;
 
-pub use callable::Callable;
-pub use environment::Environment;
+pub use callable::{Callable, NativeFunction};
+pub use class::{Class, Instance};
+pub use debugger::{Debugger, ExecutionHook};
+pub use environment::{Environment, Local};
 pub use expr::*;
-pub use interpreter::{ExprValue, Interpreter, RuntimeError};
+pub use interpreter::{
+    ControlFlow, ExprValue, Interpreter, IoPolicy, RuntimeError, DEFAULT_MAX_CALL_DEPTH,
+};
+pub use map::{Map, MapKey};
+pub use module::Module;
 pub use parser::Parser;
-pub use resolver::Resolver;
-pub use scanner::Scanner;
+pub use resolver::{Reference, Resolver, Warning};
+pub use scanner::{ScanError, Scanner};
+pub use sink::Sink;
 pub use stmt::Stmt;
 pub use token::*;
@@ -0,0 +1,686 @@
+use crate::diagnostic::Diagnostic;
+use crate::json::Json;
+use crate::{
+    Callable, Expr, Interpreter, Parser, Reference, Resolver, Scanner, Sink, Span, Stmt, Token,
+};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// A language server speaking LSP's JSON-RPC over stdio (see `src/bin/lox-lsp.rs`). Documents
+// are synced in full and re-analyzed from scratch on every request: scanning, parsing and
+// resolving a file takes far less time than an editor waits between keystrokes.
+
+// JSON-RPC error codes
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+const INVALID_PARAMS: f64 = -32602.0;
+
+// `DiagnosticSeverity`
+const ERROR: f64 = 1.0;
+const WARNING: f64 = 2.0;
+
+// `SymbolKind`
+const MODULE: f64 = 2.0;
+const CLASS: f64 = 5.0;
+const METHOD: f64 = 6.0;
+const CONSTRUCTOR: f64 = 9.0;
+const FUNCTION: f64 = 12.0;
+const VARIABLE: f64 = 13.0;
+
+/// Serves requests read from `input` until the client says to exit or hangs up, returning the
+/// exit code the protocol asks for: 0 only if the client asked to shut down first.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<i32> {
+    let mut server = Server::default();
+
+    while let Some(body) = read_message(&mut input)? {
+        let replies = match Json::parse(&body) {
+            Ok(message) => server.handle(&message),
+            Err(err) => vec![error_response(Json::Null, PARSE_ERROR, err.to_string())],
+        };
+
+        for reply in &replies {
+            write_message(&mut output, reply)?;
+        }
+
+        if server.exited {
+            break;
+        }
+    }
+
+    Ok(if server.shutdown { 0 } else { 1 })
+}
+
+/// Reads the body of one `Content-Length` framed message, `None` once the input is closed.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() && length.is_some() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; length.expect("should have read a length before the blank line")];
+    input.read_exact(&mut body)?;
+
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+struct ResponseError {
+    code: f64,
+    message: String,
+}
+
+impl ResponseError {
+    fn invalid_params(message: &str) -> Self {
+        ResponseError {
+            code: INVALID_PARAMS,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn response(id: Json, result: Json) -> Json {
+    Json::object([
+        ("jsonrpc", Json::string("2.0")),
+        ("id", id),
+        ("result", result),
+    ])
+}
+
+fn error_response(id: Json, code: f64, message: String) -> Json {
+    Json::object([
+        ("jsonrpc", Json::string("2.0")),
+        ("id", id),
+        (
+            "error",
+            Json::object([
+                ("code", Json::Number(code)),
+                ("message", Json::String(message)),
+            ]),
+        ),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string(method)),
+        ("params", params),
+    ])
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>, // uri to its current text
+    shutdown: bool,
+    exited: bool,
+}
+
+impl Server {
+    /// Handles one incoming message, returning the responses and notifications to send back.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let params = message.get("params").unwrap_or(&Json::Null);
+
+        match (
+            message.get("method").and_then(Json::as_str),
+            message.get("id"),
+        ) {
+            (Some(method), Some(id)) => {
+                let reply = match self.request(method, params) {
+                    Ok(result) => response(id.clone(), result),
+                    Err(err) => error_response(id.clone(), err.code, err.message),
+                };
+                vec![reply]
+            }
+            (Some(method), None) => self.notification(method, params),
+            // the server never sends requests, so there are no responses to wait for
+            (None, _) => vec![],
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, ResponseError> {
+        if self.shutdown {
+            return Err(ResponseError {
+                code: INVALID_REQUEST,
+                message: "Server is shutting down.".to_string(),
+            });
+        }
+
+        match method {
+            "initialize" => Ok(Json::object([
+                (
+                    "capabilities",
+                    Json::object([
+                        ("textDocumentSync", Json::Number(1.0)), // full text on every change
+                        ("definitionProvider", Json::Bool(true)),
+                        ("referencesProvider", Json::Bool(true)),
+                        ("hoverProvider", Json::Bool(true)),
+                        ("documentSymbolProvider", Json::Bool(true)),
+                    ]),
+                ),
+                (
+                    "serverInfo",
+                    Json::object([("name", Json::string("lox-lsp"))]),
+                ),
+            ])),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unknown method '{method}'."),
+            }),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|document| document.get("uri"))
+            .and_then(Json::as_str);
+
+        match (method, uri) {
+            ("exit", _) => {
+                self.exited = true;
+                vec![]
+            }
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|document| document.get("text"))
+                    .and_then(Json::as_str);
+                self.update(uri, text)
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                self.update(uri, text)
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, Vec::new())]
+            }
+            // `initialized`, `$/cancelRequest` and anything else needs no reply
+            _ => vec![],
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: Option<&str>) -> Vec<Json> {
+        let Some(text) = text else {
+            return vec![];
+        };
+
+        self.documents.insert(uri.to_string(), text.to_string());
+        vec![publish_diagnostics(uri, analyze(text).diagnostics)]
+    }
+
+    // the document and cursor offset a `TextDocumentPositionParams` points at
+    fn position_params<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a str, usize), ResponseError> {
+        let (uri, source) = self.document(params)?;
+        let offset = params
+            .get("position")
+            .and_then(|position| offset(source, position))
+            .ok_or_else(|| ResponseError::invalid_params("Missing or invalid position."))?;
+
+        Ok((uri, source, offset))
+    }
+
+    fn document<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a str), ResponseError> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|document| document.get("uri"))
+            .and_then(Json::as_str)
+            .ok_or_else(|| ResponseError::invalid_params("Missing textDocument.uri."))?;
+
+        let source = self.documents.get(uri).ok_or_else(|| {
+            ResponseError::invalid_params(&format!("Document '{uri}' isn't open."))
+        })?;
+
+        Ok((uri, source))
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (uri, source, offset) = self.position_params(params)?;
+        let analysis = analyze(source);
+
+        Ok(analysis
+            .reference_at(offset)
+            .and_then(|reference| reference.declaration)
+            .map(|declaration| location(uri, source, declaration))
+            .into())
+    }
+
+    fn references(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (uri, source, offset) = self.position_params(params)?;
+        let analysis = analyze(source);
+
+        let Some(target) = analysis.reference_at(offset) else {
+            return Ok(Json::Array(vec![]));
+        };
+
+        let include_declaration = params
+            .get("context")
+            .and_then(|context| context.get("includeDeclaration"))
+            .and_then(Json::as_bool)
+            .unwrap_or(true);
+
+        // builtins have no declaration, so their uses are only linked up by name
+        let locations = analysis
+            .references
+            .iter()
+            .filter(|reference| match target.declaration {
+                Some(declaration) => {
+                    reference.declaration == Some(declaration)
+                        && (include_declaration || reference.span != declaration)
+                }
+                None => reference.declaration.is_none() && reference.name == target.name,
+            })
+            .map(|reference| location(uri, source, reference.span))
+            .collect();
+
+        Ok(Json::Array(locations))
+    }
+
+    fn hover(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (_, source, offset) = self.position_params(params)?;
+        let analysis = analyze(source);
+
+        let mut signatures = HashMap::new();
+        collect_signatures(&analysis.statements, &mut signatures);
+
+        // methods aren't variables, so they're only found from their own declaration
+        let target = match analysis.reference_at(offset) {
+            Some(reference) => reference
+                .declaration
+                .map(|declaration| (reference.span, declaration)),
+            None => signatures
+                .keys()
+                .find(|span| contains(**span, offset))
+                .map(|span| (*span, *span)),
+        };
+
+        let Some((span, declaration)) = target else {
+            return Ok(Json::Null);
+        };
+
+        Ok(signatures
+            .get(&declaration)
+            .map(|signature| {
+                Json::object([
+                    (
+                        "contents",
+                        Json::object([
+                            ("kind", Json::string("markdown")),
+                            ("value", Json::String(format!("```lox\n{signature}\n```"))),
+                        ]),
+                    ),
+                    ("range", range(source, span)),
+                ])
+            })
+            .into())
+    }
+
+    fn document_symbols(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (_, source) = self.document(params)?;
+        let analysis = analyze(source);
+
+        let symbols = analysis
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Var(name, _) => Some(symbol(source, name, VARIABLE, None, vec![])),
+                Stmt::Function(function) => {
+                    let (name, params) = function_parts(function);
+                    Some(symbol(
+                        source,
+                        name,
+                        FUNCTION,
+                        Some(params_list(params)),
+                        vec![],
+                    ))
+                }
+                Stmt::Class {
+                    name,
+                    superclass,
+                    methods,
+                    ..
+                } => {
+                    let methods = methods
+                        .iter()
+                        .map(|method| {
+                            let (name, params) = function_parts(method);
+                            let kind = if name.lexeme == "init" {
+                                CONSTRUCTOR
+                            } else {
+                                METHOD
+                            };
+                            symbol(source, name, kind, Some(params_list(params)), vec![])
+                        })
+                        .collect();
+
+                    let superclass = superclass
+                        .as_ref()
+                        .map(|superclass| format!("< {superclass}"));
+                    Some(symbol(source, name, CLASS, superclass, methods))
+                }
+                Stmt::Import {
+                    path,
+                    name: Some(name),
+                    ..
+                } => Some(symbol(
+                    source,
+                    name,
+                    MODULE,
+                    Some(format!("\"{path}\"")),
+                    vec![],
+                )),
+                _ => None,
+            })
+            .collect();
+
+        Ok(Json::Array(symbols))
+    }
+}
+
+struct Analysis {
+    statements: Vec<Stmt>,
+    references: Vec<Reference>,
+    diagnostics: Vec<Json>,
+}
+
+impl Analysis {
+    // the cursor may be just past the end of the name, where editors leave it after typing
+    fn reference_at(&self, offset: usize) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|reference| contains(reference.span, offset))
+    }
+}
+
+fn analyze(source: &str) -> Analysis {
+    let (tokens, scan_errors) = Scanner::new(source.to_string()).scan_tokens();
+    let (statements, parse_errors) = Parser::new(tokens).parse();
+
+    let mut diagnostics: Vec<Json> = scan_errors
+        .iter()
+        .map(|err| diagnostic(source, err, ERROR))
+        .chain(
+            parse_errors
+                .iter()
+                .map(|err| diagnostic(source, err, ERROR)),
+        )
+        .collect();
+
+    // whatever did parse is still resolved, so navigation keeps working while typing
+    // they're published to the client instead
+    let mut interpreter = Interpreter::new();
+    interpreter.set_diagnostics(Sink::new(io::sink()));
+    let mut resolver = Resolver::new(&mut interpreter);
+    if let Err(err) = resolver.resolve(&statements) {
+        diagnostics.push(diagnostic(source, &err, ERROR));
+    }
+
+    // warnings about a half-parsed program would mostly be noise
+    if diagnostics.is_empty() {
+        diagnostics.extend(
+            resolver
+                .take_warnings()
+                .iter()
+                .map(|warning| diagnostic(source, warning, WARNING)),
+        );
+    }
+
+    let references = resolver.take_references();
+
+    Analysis {
+        statements,
+        references,
+        diagnostics,
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    notification(
+        "textDocument/publishDiagnostics",
+        Json::object([
+            ("uri", Json::string(uri)),
+            ("diagnostics", Json::Array(diagnostics)),
+        ]),
+    )
+}
+
+fn diagnostic(source: &str, error: &dyn Diagnostic, severity: f64) -> Json {
+    let span = error.location().map_or(Span::default(), |(_, span)| span);
+
+    // the range already says where, so the CLI's `[line N] ` prefix is dropped
+    let message = error.to_string();
+    let message = message
+        .strip_prefix("[line ")
+        .and_then(|rest| rest.split_once("] "))
+        .map_or(message.as_str(), |(_, message)| message);
+
+    Json::object([
+        ("range", range(source, span)),
+        ("severity", Json::Number(severity)),
+        ("source", Json::string("lox")),
+        ("message", Json::string(message)),
+    ])
+}
+
+fn contains(span: Span, offset: usize) -> bool {
+    span.offset <= offset && offset <= span.offset + span.len
+}
+
+fn location(uri: &str, source: &str, span: Span) -> Json {
+    Json::object([("uri", Json::string(uri)), ("range", range(source, span))])
+}
+
+fn range(source: &str, span: Span) -> Json {
+    Json::object([
+        ("start", position(source, span.offset)),
+        ("end", position(source, span.offset + span.len)),
+    ])
+}
+
+// LSP counts lines from 0 and characters in UTF-16 code units, spans count bytes
+fn position(source: &str, offset: usize) -> Json {
+    let offset = offset.min(source.len());
+    let line_start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+
+    let line = source[..offset].matches('\n').count();
+    let character: usize = source[line_start..offset]
+        .chars()
+        .map(char::len_utf16)
+        .sum();
+
+    Json::object([("line", line.into()), ("character", character.into())])
+}
+
+fn offset(source: &str, position: &Json) -> Option<usize> {
+    let line = position.get("line")?.as_usize()?;
+    let character = position.get("character")?.as_usize()?;
+
+    let line_start = match line {
+        0 => 0,
+        line => source.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+
+    let mut units = 0;
+    for (idx, c) in source[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_start + idx);
+        }
+        units += c.len_utf16();
+    }
+
+    Some(source.len())
+}
+
+fn symbol(
+    source: &str,
+    name: &Token,
+    kind: f64,
+    detail: Option<String>,
+    children: Vec<Json>,
+) -> Json {
+    // statements don't keep their full extent, so the name stands in for the whole declaration
+    Json::object([
+        ("name", Json::string(&name.lexeme)),
+        ("detail", detail.map(Json::String).into()),
+        ("kind", Json::Number(kind)),
+        ("range", range(source, name.span)),
+        ("selectionRange", range(source, name.span)),
+        ("children", Json::Array(children)),
+    ])
+}
+
+fn function_parts(function: &Callable) -> (&Token, &[Token]) {
+    match function {
+        Callable::Function { name, params, .. } => (name, params),
+        _ => unreachable!("declarations only hold `Callable::Function`"),
+    }
+}
+
+fn params_list(params: &[Token]) -> String {
+    let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+    format!("({})", params.join(", "))
+}
+
+// what hovering a name shows, keyed by the span of its declaration
+fn collect_signatures(statements: &[Stmt], signatures: &mut HashMap<Span, String>) {
+    for stmt in statements {
+        collect_stmt_signatures(stmt, signatures);
+    }
+}
+
+fn collect_stmt_signatures(stmt: &Stmt, signatures: &mut HashMap<Span, String>) {
+    match stmt {
+        Stmt::Var(name, Expr::Function { params, body, .. }) => {
+            signatures.insert(
+                name.span,
+                format!("fun {}{}", name.lexeme, params_list(params)),
+            );
+            collect_function_signatures(params, body, signatures);
+        }
+        Stmt::Var(name, _) => {
+            signatures.insert(name.span, format!("var {}", name.lexeme));
+        }
+        Stmt::Function(Callable::Function {
+            name, params, body, ..
+        }) => {
+            signatures.insert(
+                name.span,
+                format!("fun {}{}", name.lexeme, params_list(params)),
+            );
+            collect_function_signatures(params, body, signatures);
+        }
+        Stmt::Class {
+            name,
+            superclass,
+            methods,
+            ..
+        } => {
+            let superclass = superclass
+                .as_ref()
+                .map_or(String::new(), |superclass| format!(" < {superclass}"));
+            signatures.insert(name.span, format!("class {}{superclass}", name.lexeme));
+
+            for method in methods {
+                if let Callable::Function {
+                    name: method,
+                    params,
+                    body,
+                    ..
+                } = method
+                {
+                    signatures.insert(
+                        method.span,
+                        format!("{}.{}{}", name.lexeme, method.lexeme, params_list(params)),
+                    );
+                    collect_function_signatures(params, body, signatures);
+                }
+            }
+        }
+        Stmt::Block(statements, _) => collect_signatures(statements, signatures),
+        Stmt::If {
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            collect_stmt_signatures(then_branch, signatures);
+            if let Some(else_branch) = else_branch {
+                collect_stmt_signatures(else_branch, signatures);
+            }
+        }
+        Stmt::While { body, .. } => collect_stmt_signatures(body, signatures),
+        Stmt::For {
+            initializer, body, ..
+        } => {
+            if let Some(initializer) = initializer {
+                collect_stmt_signatures(initializer, signatures);
+            }
+            collect_stmt_signatures(body, signatures);
+        }
+        Stmt::Try {
+            body,
+            catch,
+            finally,
+            ..
+        } => {
+            collect_stmt_signatures(body, signatures);
+            if let Some((_, handler)) = catch {
+                collect_stmt_signatures(handler, signatures);
+            }
+            if let Some(finally) = finally {
+                collect_stmt_signatures(finally, signatures);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn collect_function_signatures(
+    params: &[Token],
+    body: &Stmt,
+    signatures: &mut HashMap<Span, String>,
+) {
+    for param in params {
+        signatures.insert(param.span, format!("(parameter) {}", param.lexeme));
+    }
+
+    collect_stmt_signatures(body, signatures);
+}
@@ -1,48 +1,174 @@
-use my_ast_interpreter::{Interpreter, Parser, Resolver, Scanner};
+use my_ast_interpreter::diagnostic::render;
+use my_ast_interpreter::{ast_printer, dump, formatter};
+use my_ast_interpreter::{
+    Debugger, Interpreter, IoPolicy, Parser, Resolver, Scanner, TokenType, Warning,
+};
+use std::cell::RefCell;
 use std::env;
 use std::fs;
+use std::io::{self, Write};
 use std::process;
+use std::rc::Rc;
+use std::thread;
+
+// parsing and resolving recurse on the native stack for every level of nesting in the source
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 fn main() {
+    let cli = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(cli)
+        .expect("should spawn the interpreter thread");
+
+    if let Err(panic) = cli.join() {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+fn cli() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        eprintln!(
-            "Usage: {} tokenize | parse | evaluate <filename> | run <filename>",
-            args[0]
-        );
 
-        return;
+    let io_policy = io_policy(&args);
+    let format = format(&args);
+    let deny_warnings = args.iter().any(|arg| arg == "--warnings-as-errors");
+
+    if args.len() >= 2 && args[1] == "repl" {
+        return repl(io_policy, deny_warnings);
     }
 
-    let command = &args[1];
-    let filename = &args[2];
+    let (Some(command), Some(filename)) = (args.get(1), filename(&args)) else {
+        eprintln!(
+            "Usage: {} tokenize | parse <filename> [--format text|json|sexpr] | evaluate <filename> | run <filename> | debug <filename> [--break <line>]... | fmt <filename> [--check] | repl [--io=all|read-only|none] [--warnings-as-errors]",
+            args[0]
+        );
+        process::exit(64);
+    };
 
-    let file_contents = fs::read_to_string(filename).unwrap_or_else(|_| {
-        eprintln!("Failed to read file {}", filename);
-        String::new()
+    let file_contents = fs::read_to_string(filename).unwrap_or_else(|err| {
+        eprintln!("Failed to read file {filename}: {err}");
+        process::exit(66);
     });
 
     match command.as_str() {
-        "tokenize" => tokenize(file_contents),
-        "parse" => parse(file_contents),
-        "evaluate" => evaluate(file_contents),
-        "run" => run(file_contents),
+        "tokenize" => tokenize(file_contents, format),
+        "parse" => parse(file_contents, format),
+        "fmt" => fmt(
+            filename,
+            file_contents,
+            args.iter().any(|arg| arg == "--check"),
+        ),
+        "evaluate" => evaluate(filename, file_contents, io_policy, deny_warnings),
+        "run" => run(filename, file_contents, io_policy, deny_warnings, None),
+        "debug" => run(
+            filename,
+            file_contents,
+            io_policy,
+            deny_warnings,
+            Some(breakpoints(&args)),
+        ),
         _ => {
             eprintln!("Unknown command: {}", command);
         }
     }
 }
 
-fn tokenize(file_contents: String) {
-    let scanner = Scanner::new(file_contents);
+// the first argument after the command that isn't a flag or a flag's value, so flags can go
+// before the file as well as after it
+fn filename(args: &[String]) -> Option<&str> {
+    let mut rest = args.iter().skip(2);
+
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--format" | "--break" => {
+                rest.next();
+            }
+            flag if flag.starts_with("--") => (),
+            filename => return Some(filename),
+        }
+    }
+
+    None
+}
+
+// `--io=<policy>` limits what scripts can do through the I/O natives
+fn io_policy(args: &[String]) -> IoPolicy {
+    let Some(policy) = args.iter().find_map(|arg| arg.strip_prefix("--io=")) else {
+        return IoPolicy::default();
+    };
+
+    IoPolicy::try_from(policy).unwrap_or_else(|err| {
+        eprintln!("Invalid --io value '{policy}': {err}");
+        process::exit(64);
+    })
+}
+
+// `--break <line>`, any number of times
+fn breakpoints(args: &[String]) -> Vec<usize> {
+    args.iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == "--break")
+        .map(|(idx, _)| {
+            let line = args.get(idx + 1).map_or("", String::as_str);
+            line.parse().unwrap_or_else(|_| {
+                eprintln!("Invalid --break value '{line}': should be a line number");
+                process::exit(64);
+            })
+        })
+        .collect()
+}
+
+// how `tokenize` and `parse` print what they found
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Sexpr, // `parse` only
+}
+
+// `--format <format>` (or `--format=<format>`)
+fn format(args: &[String]) -> Format {
+    let format = args.iter().enumerate().find_map(|(idx, arg)| {
+        if arg == "--format" {
+            Some(args.get(idx + 1).map_or("", String::as_str))
+        } else {
+            arg.strip_prefix("--format=")
+        }
+    });
+
+    match format {
+        None | Some("text") => Format::Text,
+        Some("json") => Format::Json,
+        Some("sexpr") => Format::Sexpr,
+        Some(format) => {
+            eprintln!("Invalid --format value '{format}': expected text, json or sexpr");
+            process::exit(64);
+        }
+    }
+}
+
+// the resolver has reported them already; with `--warnings-as-errors` any warning stops the
+// script like a compile error would
+fn check_warnings(warnings: &[Warning], deny_warnings: bool) {
+    if deny_warnings && !warnings.is_empty() {
+        process::exit(65);
+    }
+}
+
+fn tokenize(file_contents: String, format: Format) {
+    let scanner = Scanner::new(file_contents.clone());
     let (tokens, errors) = scanner.scan_tokens();
 
     for error in &errors {
-        eprintln!("{}", error)
+        eprintln!("{}", render(&file_contents, error))
     }
 
-    for token in tokens {
-        println!("{}", token)
+    match format {
+        Format::Text => tokens.iter().for_each(|token| println!("{}", token)),
+        Format::Json => println!("{}", dump::tokens(&tokens)),
+        Format::Sexpr => {
+            eprintln!("--format sexpr is only supported by parse");
+            process::exit(64);
+        }
     }
 
     if !errors.is_empty() {
@@ -50,12 +176,12 @@ fn tokenize(file_contents: String) {
     }
 }
 
-fn parse(file_contents: String) {
-    let scanner = Scanner::new(file_contents);
+fn parse(file_contents: String, format: Format) {
+    let scanner = Scanner::new(file_contents.clone());
     let (tokens, errors) = scanner.scan_tokens();
 
     for error in &errors {
-        eprintln!("{}", error)
+        eprintln!("{}", render(&file_contents, error))
     }
 
     if !errors.is_empty() {
@@ -63,24 +189,65 @@ fn parse(file_contents: String) {
     }
 
     let mut parser = Parser::new(tokens);
+    let (statements, parse_errors) = parser.parse();
 
-    match parser.parse() {
-        Ok(statements) => statements
+    for parse_err in &parse_errors {
+        eprintln!("{}", render(&file_contents, parse_err));
+    }
+
+    if !parse_errors.is_empty() {
+        process::exit(65);
+    }
+
+    match format {
+        Format::Text => statements
             .iter()
             .for_each(|statement| println!("{statement}")),
-        Err(parse_err) => {
-            eprintln!("{}", parse_err);
-            process::exit(65);
-        }
+        Format::Json => println!("{}", dump::program(&statements)),
+        Format::Sexpr => print!("{}", ast_printer::print(&statements)),
     }
 }
 
-fn evaluate(file_contents: String) {
-    let scanner = Scanner::new(file_contents);
+// prints the formatted source, or with `--check` only reports whether it's formatted already
+fn fmt(filename: &str, file_contents: String, check: bool) {
+    let scanner = Scanner::new(file_contents.clone());
+    let (tokens, comments, errors) = scanner.scan_with_comments();
+
+    for error in &errors {
+        eprintln!("{}", render(&file_contents, error))
+    }
+
+    if !errors.is_empty() {
+        process::exit(65)
+    }
+
+    let (statements, parse_errors) = Parser::new(tokens).parse();
+
+    for parse_err in &parse_errors {
+        eprintln!("{}", render(&file_contents, parse_err));
+    }
+
+    if !parse_errors.is_empty() {
+        process::exit(65);
+    }
+
+    let formatted = formatter::format_with_comments(&statements, &comments);
+
+    if !check {
+        print!("{formatted}");
+    } else if formatted != file_contents {
+        eprintln!("{filename} is not formatted");
+        process::exit(1);
+    }
+}
+
+fn evaluate(filename: &str, file_contents: String, io_policy: IoPolicy, deny_warnings: bool) {
+    let scanner = Scanner::new(file_contents.clone());
+    let source_id = scanner.source_id();
     let (tokens, errors) = scanner.scan_tokens();
 
     for error in &errors {
-        eprintln!("{}", error)
+        eprintln!("{}", render(&file_contents, error))
     }
 
     if !errors.is_empty() {
@@ -88,38 +255,49 @@ fn evaluate(file_contents: String) {
     }
 
     let mut parser = Parser::new(tokens);
+    let (statements, parse_errors) = parser.parse();
 
-    match parser.parse() {
-        Ok(statements) => {
-            let mut interpreter = Interpreter::new();
+    for parse_err in &parse_errors {
+        eprintln!("{}", render(&file_contents, parse_err));
+    }
 
-            let mut resolver = Resolver::new(&mut interpreter);
-            if let Err(err) = resolver.resolve(&statements) {
-                eprintln!("{err}");
-                process::exit(65);
-            }
+    if !parse_errors.is_empty() {
+        process::exit(65);
+    }
 
-            match interpreter.interpret(statements) {
-                Ok(_) => (),
-                Err(runtime_err) => {
-                    eprintln!("{}", runtime_err);
-                    process::exit(70);
-                }
-            }
-        }
-        Err(parse_err) => {
-            eprintln!("{}", parse_err);
-            process::exit(65);
-        }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_path(filename);
+    interpreter.set_io_policy(io_policy);
+    interpreter.add_source(source_id, &file_contents);
+
+    let mut resolver = Resolver::new(&mut interpreter);
+    if let Err(err) = resolver.resolve(&statements) {
+        resolver.interpreter.report(&err);
+        process::exit(65);
+    }
+
+    check_warnings(&resolver.take_warnings(), deny_warnings);
+
+    // the interpreter reports its own errors
+    if interpreter.interpret(statements).is_err() {
+        process::exit(70);
     }
 }
 
-fn run(file_contents: String) {
-    let scanner = Scanner::new(file_contents);
+// `debug` is `run` with breakpoints, pausing before the first statement
+fn run(
+    filename: &str,
+    file_contents: String,
+    io_policy: IoPolicy,
+    deny_warnings: bool,
+    debug: Option<Vec<usize>>,
+) {
+    let scanner = Scanner::new(file_contents.clone());
+    let source_id = scanner.source_id();
     let (tokens, errors) = scanner.scan_tokens();
 
     for error in &errors {
-        eprintln!("{}", error)
+        eprintln!("{}", render(&file_contents, error))
     }
 
     if !errors.is_empty() {
@@ -127,32 +305,164 @@ fn run(file_contents: String) {
     }
 
     let mut parser = Parser::new(tokens);
+    let (statements, parse_errors) = parser.parse();
 
-    match parser.parse() {
-        Ok(statements) => {
-            let mut interpreter = Interpreter::new();
+    for parse_err in &parse_errors {
+        eprintln!("{}", render(&file_contents, parse_err));
+    }
 
-            let mut resolver = Resolver::new(&mut interpreter);
+    if !parse_errors.is_empty() {
+        process::exit(65);
+    }
 
-            if let Err(err) = resolver.resolve(&statements) {
-                eprintln!("{err}");
-                process::exit(65);
-            }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_path(filename);
+    interpreter.set_io_policy(io_policy);
+    interpreter.add_source(source_id, &file_contents);
+
+    let mut resolver = Resolver::new(&mut interpreter);
 
-            interpreter
-                .set_status("run")
-                .expect("should set interpreter status::run");
-            match interpreter.interpret(statements) {
-                Ok(_) => (),
-                Err(runtime_err) => {
-                    eprintln!("{}", runtime_err);
-                    process::exit(70);
-                }
+    if let Err(err) = resolver.resolve(&statements) {
+        resolver.interpreter.report(&err);
+        process::exit(65);
+    }
+
+    check_warnings(&resolver.take_warnings(), deny_warnings);
+
+    interpreter
+        .set_status("run")
+        .expect("should set interpreter status::run");
+
+    let debugger = debug.map(|breakpoints| {
+        let mut debugger = Debugger::new(&file_contents, StdinLines::default(), io::stderr());
+        for line in breakpoints {
+            debugger.add_breakpoint(line);
+        }
+        Rc::new(RefCell::new(debugger))
+    });
+    if let Some(debugger) = &debugger {
+        interpreter.set_hook(Some(debugger.clone()));
+    }
+
+    // the interpreter reports its own errors
+    match interpreter.interpret(statements) {
+        Ok(_) => (),
+        Err(_) if debugger.is_some_and(|debugger| debugger.borrow().has_quit()) => (),
+        Err(_) => process::exit(70),
+    }
+}
+
+// the debugger's commands, read a line at a time so whatever follows is left for the program
+#[derive(Default)]
+struct StdinLines {
+    line: Vec<u8>,
+    read: usize,
+}
+
+impl io::Read for StdinLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = io::BufRead::fill_buf(self)?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        io::BufRead::consume(self, len);
+        Ok(len)
+    }
+}
+
+impl io::BufRead for StdinLines {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.read == self.line.len() {
+            let mut line = String::new();
+            io::stdin().read_line(&mut line)?;
+            self.line = line.into_bytes();
+            self.read = 0;
+        }
+        Ok(&self.line[self.read..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.read += amount;
+    }
+}
+
+fn repl(io_policy: IoPolicy, deny_warnings: bool) {
+    // single interpreter for the whole session so globals and resolved locals persist
+    let mut interpreter = Interpreter::new();
+    interpreter.set_io_policy(io_policy);
+    interpreter
+        .set_status("repl")
+        .expect("should set interpreter status::repl");
+
+    let stdin = io::stdin();
+    let mut buffer = String::new();
+
+    loop {
+        print!("{}", if buffer.is_empty() { "> " } else { "... " });
+        io::stdout().flush().expect("should flush repl prompt");
+
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            Ok(0) => {
+                println!();
+                return;
             }
+            Ok(_) => buffer.push_str(&line),
+            Err(err) => {
+                eprintln!("Failed to read input: {err}");
+                return;
+            }
+        }
+
+        let source = buffer.clone();
+        let scanner = Scanner::new(source.clone());
+        let source_id = scanner.source_id();
+        let (tokens, errors) = scanner.scan_tokens();
+
+        // keep reading lines until every opened brace has been closed
+        let depth = tokens
+            .iter()
+            .fold(0_isize, |depth, token| match token.token_type {
+                TokenType::LEFTBRACE => depth + 1,
+                TokenType::RIGHTBRACE => depth - 1,
+                _ => depth,
+            });
+
+        if depth > 0 {
+            continue;
+        }
+
+        buffer.clear();
+
+        if !errors.is_empty() {
+            errors
+                .iter()
+                .for_each(|error| eprintln!("{}", render(&source, error)));
+            continue;
+        }
+
+        let (statements, parse_errors) = Parser::new(tokens).parse();
+
+        if !parse_errors.is_empty() {
+            parse_errors
+                .iter()
+                .for_each(|error| eprintln!("{}", render(&source, error)));
+            continue;
         }
-        Err(parse_err) => {
-            eprintln!("{}", parse_err);
-            process::exit(65);
+
+        // warnings and errors from here on are reported by the interpreter
+        interpreter.add_source(source_id, &source);
+
+        let mut resolver = Resolver::new(&mut interpreter);
+        if let Err(err) = resolver.resolve(&statements) {
+            resolver.interpreter.report(&err);
+            continue;
         }
+
+        // with `--warnings-as-errors` an entry with warnings is rejected like a compile error
+        if deny_warnings && !resolver.take_warnings().is_empty() {
+            continue;
+        }
+
+        let _ = interpreter.interpret(statements);
     }
 }
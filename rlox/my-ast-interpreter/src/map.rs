@@ -0,0 +1,115 @@
+use crate::{interpreter::display_once, ExprValue};
+use std::{collections::HashMap, fmt::Display};
+
+// only primitives can be keys, found by the same comparison `Interpreter::is_equal` makes for
+// those types: exact, so `0.1 + 0.2` isn't `0.3`. NaN is the exception, a key like any other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Bool(bool),
+    Number(u64), // f64 bits, with -0 folded into 0 so they hash alike
+    String(String),
+    Nil,
+}
+
+impl MapKey {
+    pub fn new(value: &ExprValue) -> Option<Self> {
+        match value {
+            ExprValue::Bool(b) => Some(MapKey::Bool(*b)),
+            ExprValue::Number(n) if *n == 0.0 => Some(MapKey::Number(0.0_f64.to_bits())),
+            ExprValue::Number(n) => Some(MapKey::Number(n.to_bits())),
+            ExprValue::String(s) => Some(MapKey::String(s.clone())),
+            ExprValue::Nil => Some(MapKey::Nil),
+            _ => None,
+        }
+    }
+
+    pub fn to_value(&self) -> ExprValue {
+        match self {
+            MapKey::Bool(b) => ExprValue::Bool(*b),
+            MapKey::Number(bits) => ExprValue::Number(f64::from_bits(*bits)),
+            MapKey::String(s) => ExprValue::String(s.clone()),
+            MapKey::Nil => ExprValue::Nil,
+        }
+    }
+}
+
+// entries are kept in insertion order; `index` points each key at its slot in `entries`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Map {
+    entries: Vec<(MapKey, ExprValue)>,
+    index: HashMap<MapKey, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<&ExprValue> {
+        self.index.get(key).map(|&idx| &self.entries[idx].1)
+    }
+
+    pub fn contains_key(&self, key: &MapKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    // overwriting an existing key keeps its original position
+    pub fn insert(&mut self, key: MapKey, value: ExprValue) {
+        match self.index.get(&key) {
+            Some(&idx) => self.entries[idx].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &MapKey) -> Option<ExprValue> {
+        let idx = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(idx);
+
+        // everything after the removed entry shifted down one slot
+        for (key, _) in &self.entries[idx..] {
+            if let Some(slot) = self.index.get_mut(key) {
+                *slot -= 1;
+            }
+        }
+
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&MapKey, &ExprValue)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl Display for MapKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let displayed = display_once((self as *const Map).cast(), || {
+            write!(f, "{{")?;
+            for (i, (key, value)) in self.entries.iter().enumerate() {
+                if i < self.entries.len() - 1 {
+                    write!(f, "{}: {}, ", key, value)?;
+                } else {
+                    write!(f, "{}: {}", key, value)?;
+                }
+            }
+            write!(f, "}}")
+        });
+        displayed.unwrap_or_else(|| write!(f, "{{...}}"))
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{Environment, ExprValue, RuntimeError, Token};
+use std::{cell::RefCell, fmt::Display, path::PathBuf, rc::Rc};
+
+// an imported file: its top-level declarations live in `environment`, which is only ever
+// executed once no matter how many times (or from where) the file is imported
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+impl Module {
+    pub fn new(path: PathBuf, environment: Rc<RefCell<Environment>>) -> Self {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+
+        Module {
+            name,
+            path,
+            environment,
+        }
+    }
+
+    // only the module's own declarations (and unnamespaced imports) are exported, not the
+    // builtins it can see
+    pub fn get(&self, name: &Token) -> Result<ExprValue, RuntimeError> {
+        match self.environment.borrow().exported(&name.lexeme) {
+            Some(value) => Ok(value),
+            None => Err(RuntimeError::RTE {
+                token: name.lexeme.clone(),
+                message: format!("Undefined property '{}'.", name.lexeme),
+                line: name.line,
+                span: name.span,
+            }),
+        }
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{Callable, ExprValue, Interpreter, IoPolicy, Map, MapKey, RuntimeError};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{cell::RefCell, rc::Rc};
+
+// builtins every interpreter starts with, registered into `globals` by `Interpreter::new`
+pub fn define_core(interpreter: &mut Interpreter) {
+    interpreter.define_native("clock", 0, clock);
+    interpreter.define_native("typeOf", 1, type_of);
+    interpreter.define_native("assert", 2, assert);
+    interpreter.define_native("len", 1, len);
+    interpreter.define_native("push", 2, push);
+    interpreter.define_native("pop", 1, pop);
+    interpreter.define_native("keys", 1, keys);
+    interpreter.define_native("values", 1, values);
+    interpreter.define_native("has", 2, has);
+    interpreter.define_native("remove", 2, remove);
+
+    define_strings(interpreter);
+    define_math(interpreter);
+    define_io(interpreter);
+}
+
+// always registered, `IoPolicy` is checked per call so denied scripts get a clear error
+fn define_io(interpreter: &mut Interpreter) {
+    interpreter.define_native("readLine", 0, read_line);
+    interpreter.define_native("readFile", 1, read_file);
+    interpreter.define_native("writeFile", 2, |interpreter, args| {
+        write_file(interpreter, args, "writeFile", false)
+    });
+    interpreter.define_native("appendFile", 2, |interpreter, args| {
+        write_file(interpreter, args, "appendFile", true)
+    });
+    interpreter.define_native("fileExists", 1, file_exists);
+}
+
+fn define_strings(interpreter: &mut Interpreter) {
+    interpreter.define_native("substr", 3, substr);
+    interpreter.define_native("indexOf", 2, index_of);
+    interpreter.define_native("split", 2, split);
+    interpreter.define_native("upper", 1, |_, args| {
+        Ok(ExprValue::String(
+            string_arg("upper", args, 0)?.to_uppercase(),
+        ))
+    });
+    interpreter.define_native("lower", 1, |_, args| {
+        Ok(ExprValue::String(
+            string_arg("lower", args, 0)?.to_lowercase(),
+        ))
+    });
+    interpreter.define_native("trim", 1, |_, args| {
+        Ok(ExprValue::String(
+            string_arg("trim", args, 0)?.trim().to_string(),
+        ))
+    });
+    interpreter.define_native("toNumber", 1, to_number);
+    interpreter.define_native("toString", 1, |_, args| {
+        Ok(ExprValue::String(args[0].to_string()))
+    });
+}
+
+fn define_math(interpreter: &mut Interpreter) {
+    define_unary_math(interpreter, "abs", f64::abs);
+    define_unary_math(interpreter, "floor", f64::floor);
+    define_unary_math(interpreter, "ceil", f64::ceil);
+    define_unary_math(interpreter, "sqrt", f64::sqrt);
+    define_unary_math(interpreter, "sin", f64::sin);
+    define_unary_math(interpreter, "cos", f64::cos);
+    define_binary_math(interpreter, "pow", f64::powf);
+    define_binary_math(interpreter, "min", f64::min);
+    define_binary_math(interpreter, "max", f64::max);
+}
+
+fn define_unary_math(interpreter: &mut Interpreter, name: &'static str, op: fn(f64) -> f64) {
+    interpreter.define_native(name, 1, move |_, args| {
+        Ok(ExprValue::Number(op(number_arg(name, args, 0)?)))
+    });
+}
+
+fn define_binary_math(interpreter: &mut Interpreter, name: &'static str, op: fn(f64, f64) -> f64) {
+    interpreter.define_native(name, 2, move |_, args| {
+        let (a, b) = (number_arg(name, args, 0)?, number_arg(name, args, 1)?);
+        Ok(ExprValue::Number(op(a, b)))
+    });
+}
+
+fn clock(_: &mut Interpreter, _: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| RuntimeError::Native(err.to_string()))?;
+
+    Ok(ExprValue::Number(elapsed.as_secs_f64()))
+}
+
+fn type_of(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let type_name = match &args[0] {
+        ExprValue::Bool(_) => "bool",
+        ExprValue::Number(_) => "number",
+        ExprValue::String(_) => "string",
+        ExprValue::Nil => "nil",
+        ExprValue::Call(Callable::Class(_)) => "class",
+        ExprValue::Call(_) => "function",
+        ExprValue::Instance(_) => "instance",
+        ExprValue::List(_) => "list",
+        ExprValue::Map(_) => "map",
+        ExprValue::Module(_) => "module",
+    };
+
+    Ok(ExprValue::String(type_name.to_string()))
+}
+
+fn assert(interpreter: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    if interpreter.is_truthy(&args[0]) {
+        Ok(ExprValue::Nil)
+    } else {
+        Err(RuntimeError::Native(format!(
+            "Assertion failed: {}",
+            args[1]
+        )))
+    }
+}
+
+fn len(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    match &args[0] {
+        ExprValue::List(elements) => Ok(ExprValue::Number(elements.borrow().len() as f64)),
+        ExprValue::Map(map) => Ok(ExprValue::Number(map.borrow().len() as f64)),
+        ExprValue::String(s) => Ok(ExprValue::Number(s.chars().count() as f64)),
+        _ => Err(RuntimeError::Native(
+            "len() expects a list, map or string.".to_string(),
+        )),
+    }
+}
+
+fn push(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    match &args[0] {
+        ExprValue::List(elements) => {
+            elements.borrow_mut().push(args[1].clone());
+            Ok(ExprValue::Nil)
+        }
+        _ => Err(RuntimeError::Native("push() expects a list.".to_string())),
+    }
+}
+
+fn pop(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    match &args[0] {
+        ExprValue::List(elements) => elements
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| RuntimeError::Native("Can't pop from an empty list.".to_string())),
+        _ => Err(RuntimeError::Native("pop() expects a list.".to_string())),
+    }
+}
+
+fn map_arg<'a>(name: &str, arg: &'a ExprValue) -> Result<&'a Rc<RefCell<Map>>, RuntimeError> {
+    match arg {
+        ExprValue::Map(map) => Ok(map),
+        _ => Err(RuntimeError::Native(format!("{name}() expects a map."))),
+    }
+}
+
+fn key_arg(arg: &ExprValue) -> Result<MapKey, RuntimeError> {
+    MapKey::new(arg).ok_or_else(|| {
+        RuntimeError::Native("Map keys must be strings, numbers, booleans or nil.".to_string())
+    })
+}
+
+fn keys(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let map = map_arg("keys", &args[0])?;
+    let keys = map.borrow().iter().map(|(key, _)| key.to_value()).collect();
+
+    Ok(ExprValue::List(Rc::new(RefCell::new(keys))))
+}
+
+fn values(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let map = map_arg("values", &args[0])?;
+    let values = map
+        .borrow()
+        .iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+
+    Ok(ExprValue::List(Rc::new(RefCell::new(values))))
+}
+
+fn has(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let map = map_arg("has", &args[0])?;
+    let key = key_arg(&args[1])?;
+
+    Ok(ExprValue::Bool(map.borrow().contains_key(&key)))
+}
+
+// returns the removed value, or nil when the key wasn't there
+fn remove(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let map = map_arg("remove", &args[0])?;
+    let key = key_arg(&args[1])?;
+    let removed = map.borrow_mut().remove(&key);
+
+    Ok(removed.unwrap_or(ExprValue::Nil))
+}
+
+// argument checks, `idx` is 0-based but reported 1-based to match how scripts read
+fn number_arg(name: &str, args: &[ExprValue], idx: usize) -> Result<f64, RuntimeError> {
+    match &args[idx] {
+        ExprValue::Number(n) => Ok(*n),
+        _ => Err(RuntimeError::Native(format!(
+            "{name}() expects argument {} to be a number.",
+            idx + 1
+        ))),
+    }
+}
+
+fn integer_arg(name: &str, args: &[ExprValue], idx: usize) -> Result<usize, RuntimeError> {
+    match number_arg(name, args, idx)? {
+        n if n.is_finite() && n >= 0.0 && n.fract() == 0.0 => Ok(n as usize),
+        _ => Err(RuntimeError::Native(format!(
+            "{name}() expects argument {} to be a non-negative integer.",
+            idx + 1
+        ))),
+    }
+}
+
+fn string_arg<'a>(name: &str, args: &'a [ExprValue], idx: usize) -> Result<&'a str, RuntimeError> {
+    match &args[idx] {
+        ExprValue::String(s) => Ok(s),
+        _ => Err(RuntimeError::Native(format!(
+            "{name}() expects argument {} to be a string.",
+            idx + 1
+        ))),
+    }
+}
+
+// substr(string, start, length), counted in characters
+fn substr(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let string = string_arg("substr", args, 0)?;
+    let start = integer_arg("substr", args, 1)?;
+    let length = integer_arg("substr", args, 2)?;
+
+    // both can be huge (`integer_arg` saturates), so their sum can overflow
+    if start
+        .checked_add(length)
+        .is_none_or(|end| end > string.chars().count())
+    {
+        return Err(RuntimeError::Native(
+            "substr() range is out of bounds.".to_string(),
+        ));
+    }
+
+    Ok(ExprValue::String(
+        string.chars().skip(start).take(length).collect(),
+    ))
+}
+
+// character index of the first match, or -1
+fn index_of(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let string = string_arg("indexOf", args, 0)?;
+    let needle = string_arg("indexOf", args, 1)?;
+
+    let index = string
+        .find(needle)
+        .map_or(-1.0, |byte_idx| string[..byte_idx].chars().count() as f64);
+
+    Ok(ExprValue::Number(index))
+}
+
+// an empty separator splits into characters
+fn split(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    let string = string_arg("split", args, 0)?;
+    let separator = string_arg("split", args, 1)?;
+
+    let parts = if separator.is_empty() {
+        string
+            .chars()
+            .map(|c| ExprValue::String(c.to_string()))
+            .collect()
+    } else {
+        string
+            .split(separator)
+            .map(|part| ExprValue::String(part.to_string()))
+            .collect()
+    };
+
+    Ok(ExprValue::List(Rc::new(RefCell::new(parts))))
+}
+
+// nil when the string isn't a number, so scripts can validate input without a try
+fn to_number(_: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    match &args[0] {
+        ExprValue::Number(n) => Ok(ExprValue::Number(*n)),
+        ExprValue::String(s) => {
+            let s = s.trim();
+            Ok(match is_number_literal(s.strip_prefix('-').unwrap_or(s)) {
+                true => ExprValue::Number(s.parse().expect("should parse a number literal")),
+                false => ExprValue::Nil,
+            })
+        }
+        _ => Err(RuntimeError::Native(
+            "toNumber() expects a number or a string.".to_string(),
+        )),
+    }
+}
+
+// Lox's own syntax, digits with an optional fraction: not Rust's, which also takes "inf",
+// "NaN", exponents and a leading '+'
+fn is_number_literal(s: &str) -> bool {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+
+    match s.split_once('.') {
+        Some((whole, fraction)) => digits(whole) && digits(fraction),
+        None => digits(s),
+    }
+}
+
+fn check_io(interpreter: &Interpreter, name: &str, writes: bool) -> Result<(), RuntimeError> {
+    match (interpreter.io_policy(), writes) {
+        (IoPolicy::Allow, _) | (IoPolicy::ReadOnly, false) => Ok(()),
+        (IoPolicy::ReadOnly, true) => Err(RuntimeError::Native(format!(
+            "{name}() is not permitted: I/O is read-only."
+        ))),
+        (IoPolicy::Deny, _) => Err(RuntimeError::Native(format!(
+            "{name}() is not permitted: I/O is disabled."
+        ))),
+    }
+}
+
+// next line of stdin without its line ending, or nil once input is exhausted
+fn read_line(interpreter: &mut Interpreter, _: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    check_io(interpreter, "readLine", false)?;
+
+    let mut line = String::new();
+    let read = io::stdin()
+        .read_line(&mut line)
+        .map_err(|err| RuntimeError::Native(format!("readLine() failed: {err}.")))?;
+
+    if read == 0 {
+        return Ok(ExprValue::Nil);
+    }
+
+    let trimmed = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(trimmed);
+
+    Ok(ExprValue::String(line))
+}
+
+fn read_file(interpreter: &mut Interpreter, args: &[ExprValue]) -> Result<ExprValue, RuntimeError> {
+    check_io(interpreter, "readFile", false)?;
+    let path = string_arg("readFile", args, 0)?;
+
+    fs::read_to_string(path)
+        .map(ExprValue::String)
+        .map_err(|err| RuntimeError::Native(format!("readFile() failed for '{path}': {err}.")))
+}
+
+fn write_file(
+    interpreter: &mut Interpreter,
+    args: &[ExprValue],
+    name: &str,
+    append: bool,
+) -> Result<ExprValue, RuntimeError> {
+    check_io(interpreter, name, true)?;
+    let path = string_arg(name, args, 0)?;
+    let contents = string_arg(name, args, 1)?;
+
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|err| RuntimeError::Native(format!("{name}() failed for '{path}': {err}.")))?;
+
+    Ok(ExprValue::Nil)
+}
+
+fn file_exists(
+    interpreter: &mut Interpreter,
+    args: &[ExprValue],
+) -> Result<ExprValue, RuntimeError> {
+    check_io(interpreter, "fileExists", false)?;
+    let path = string_arg("fileExists", args, 0)?;
+
+    Ok(ExprValue::Bool(std::path::Path::new(path).exists()))
+}
@@ -1,14 +1,19 @@
 use crate::{Callable, Environment};
-use crate::{Expr, Stmt, Token, TokenLiteral, TokenType};
+use crate::{Expr, Span, Stmt, Token, TokenLiteral, TokenType};
 use std::{cell::RefCell, rc::Rc};
 
 #[derive(Debug, thiserror::Error)]
-#[error("{0}")]
-pub struct ParseError(String);
+#[error("{message}")]
+pub struct ParseError {
+    message: String,
+    pub line: usize,
+    pub span: Span,
+}
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParseError>,
 }
 
 pub type ParseResult = Result<Expr, ParseError>;
@@ -16,31 +21,80 @@ pub type ParseStmtResult = Result<Stmt, ParseError>;
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+        }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
+    // recovers at statement boundaries: returns every statement that parsed alongside every error
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<ParseError>) {
         let mut statements: Vec<Stmt> = Vec::new();
 
         while !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
         }
 
-        Ok(statements)
+        (statements, std::mem::take(&mut self.errors))
     }
 
-    fn declaration(&mut self) -> ParseStmtResult {
-        if self.match_types(&[TokenType::VAR]) {
+    fn declaration(&mut self) -> Option<Stmt> {
+        let declaration = if self.match_types(&[TokenType::CLASS]) {
+            self.class_declaration()
+        } else if self.match_types(&[TokenType::VAR]) {
             self.var_declaration()
-        } else if self.match_types(&[TokenType::FUN]) {
-            self.function("function")
+        } else if self.check_keyword("import")
+            && (self.check_next(&TokenType::STRING) || self.check_next(&TokenType::IDENTIFIER))
+        {
+            // a variable can't be followed by a string or a name, so this can't be an expression
+            self.advance();
+            self.import_declaration()
+        } else if self.check(&TokenType::FUN) && !self.check_next(&TokenType::LEFTPAREN) {
+            // `fun (` starts an anonymous function expression, not a declaration
+            self.advance();
+            self.function("function").map(Stmt::Function)
         } else {
             self.statement()
+        };
+
+        match declaration {
+            Ok(stmt) => Some(stmt),
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize();
+                None
+            }
         }
-        // .map_err(|err| {
-        .inspect_err(|_| {
-            self.synchronize();
-            // err;
+    }
+
+    fn class_declaration(&mut self) -> ParseStmtResult {
+        let name = self.consume(&TokenType::IDENTIFIER, "Expect class name.")?;
+
+        let superclass = if self.match_types(&[TokenType::LESS]) {
+            self.consume(&TokenType::IDENTIFIER, "Expect superclass name.")?;
+            Some(Expr::Variable(self.previous().clone()))
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::LEFTBRACE, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+
+        while !self.check(&TokenType::RIGHTBRACE) && !self.is_at_end() {
+            methods.push(self.function("method")?);
+        }
+
+        let right_brace = self.consume(&TokenType::RIGHTBRACE, "Expect '}' after class body.")?;
+
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+            right_brace,
         })
     }
 
@@ -66,6 +120,21 @@ impl Parser {
             self.print_statement()
         } else if self.match_types(&[TokenType::RETURN]) {
             self.return_statement()
+        } else if self.match_types(&[TokenType::BREAK]) {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::SEMICOLON, "Expect ';' after 'break'.")?;
+            Ok(Stmt::Break(keyword))
+        } else if self.match_types(&[TokenType::CONTINUE]) {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::SEMICOLON, "Expect ';' after 'continue'.")?;
+            Ok(Stmt::Continue(keyword))
+        } else if self.match_types(&[TokenType::THROW]) {
+            let keyword = self.previous().clone();
+            let value = self.expression()?;
+            self.consume(&TokenType::SEMICOLON, "Expect ';' after thrown value.")?;
+            Ok(Stmt::Throw(keyword, value))
+        } else if self.match_types(&[TokenType::TRY]) {
+            self.try_statement()
         } else if self.match_types(&[TokenType::WHILE]) {
             self.while_statement()
         } else if self.match_types(&[TokenType::FOR]) {
@@ -79,7 +148,47 @@ impl Parser {
         }
     }
 
+    fn try_statement(&mut self) -> ParseStmtResult {
+        let keyword = self.previous().clone();
+
+        self.consume(&TokenType::LEFTBRACE, "Expect '{' after 'try'.")?;
+        let body = Box::new(self.block()?);
+
+        let catch = if self.match_types(&[TokenType::CATCH]) {
+            self.consume(&TokenType::LEFTPAREN, "Expect '(' after 'catch'.")?;
+            let name = self.consume(&TokenType::IDENTIFIER, "Expect exception variable name.")?;
+            self.consume(
+                &TokenType::RIGHTPAREN,
+                "Expect ')' after exception variable.",
+            )?;
+            self.consume(&TokenType::LEFTBRACE, "Expect '{' before catch body.")?;
+
+            Some((name, Box::new(self.block()?)))
+        } else {
+            None
+        };
+
+        let finally = if self.match_types(&[TokenType::FINALLY]) {
+            self.consume(&TokenType::LEFTBRACE, "Expect '{' after 'finally'.")?;
+            Some(Box::new(self.block()?))
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error(&keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+
+        Ok(Stmt::Try {
+            keyword,
+            body,
+            catch,
+            finally,
+        })
+    }
+
     fn for_statement(&mut self) -> ParseStmtResult {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LEFTPAREN, "Expect '(' after 'for'.")?;
 
         let mut initializer = None;
@@ -110,41 +219,19 @@ impl Parser {
 
         self.consume(&TokenType::RIGHTPAREN, "Expect ')' after 'for' clauses.")?;
 
-        let mut body = self.statement()?;
+        let body = self.statement()?;
 
-        body = match body {
-            Stmt::Block(stmts) => {
-                let mut statements = stmts.clone();
-
-                if let Some(increment) = increment {
-                    statements.push(Stmt::Expression(increment));
-                }
-
-                if condition.is_none() {
-                    condition.replace(Expr::Bool(true));
-                }
-
-                let while_body = Stmt::While {
-                    condition: condition.expect("condition should be some"),
-                    body: Box::new(Stmt::Block(statements.clone())),
-                };
-
-                if initializer.is_some() {
-                    Stmt::Block(vec![
-                        initializer.expect("initializer should be some"),
-                        while_body,
-                    ])
-                } else {
-                    while_body
-                }
-            }
-            _ => unreachable!(),
-        };
-
-        Ok(body)
+        Ok(Stmt::For {
+            keyword,
+            initializer: initializer.map(Box::new),
+            condition,
+            increment,
+            body: Box::new(body),
+        })
     }
 
     fn while_statement(&mut self) -> ParseStmtResult {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LEFTPAREN, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(
@@ -154,10 +241,15 @@ impl Parser {
 
         let body = Box::new(self.statement()?);
 
-        Ok(Stmt::While { condition, body })
+        Ok(Stmt::While {
+            keyword,
+            condition,
+            body,
+        })
     }
 
     fn if_statement(&mut self) -> ParseStmtResult {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LEFTPAREN, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
         self.consume(&TokenType::RIGHTPAREN, "Expect ')' after 'if' condition.")?;
@@ -170,6 +262,7 @@ impl Parser {
         }
 
         Ok(Stmt::If {
+            keyword,
             condition,
             then_branch,
             else_branch,
@@ -177,13 +270,14 @@ impl Parser {
     }
 
     fn print_statement(&mut self) -> ParseStmtResult {
+        let keyword = self.previous().clone();
         let val = self.expression()?;
         // self.consume(&TokenType::SEMICOLON, "Expect ';' after value.")?;
         // - want to be able to parse expr even without ';' (re: stmt) if valid syntax
         // - evaluation stage should provide RTE instead)
         self.match_types(&[TokenType::SEMICOLON]);
 
-        Ok(Stmt::Print(val))
+        Ok(Stmt::Print(keyword, val))
     }
 
     fn return_statement(&mut self) -> ParseStmtResult {
@@ -204,10 +298,33 @@ impl Parser {
         let expr = self.expression()?;
         self.match_types(&[TokenType::SEMICOLON]);
 
-        Ok(Stmt::Expression(expr))
+        Ok(Stmt::Expression(expr, self.previous().clone()))
     }
 
-    fn function(&mut self, kind: &str) -> ParseStmtResult {
+    fn import_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let path = self.consume(&TokenType::STRING, "Expect module path after 'import'.")?;
+        let Some(TokenLiteral::String(path)) = path.literal else {
+            unreachable!("string tokens always carry a string literal")
+        };
+
+        let name = if self.check_keyword("as") {
+            self.advance();
+            Some(self.consume(&TokenType::IDENTIFIER, "Expect namespace name after 'as'.")?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::SEMICOLON, "Expect ';' after import.")?;
+
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            name,
+        })
+    }
+
+    fn function(&mut self, kind: &str) -> Result<Callable, ParseError> {
         let name = self.consume(&TokenType::IDENTIFIER, &format!("Expect {kind} name."))?;
 
         self.consume(
@@ -215,14 +332,39 @@ impl Parser {
             &format!("Expect '(' after {kind} name."),
         )?;
 
+        let (params, body) = self.function_rest(kind)?;
+
+        Ok(Callable::Function {
+            name,
+            params,
+            body,
+            closure: Rc::new(RefCell::new(Environment::new())),
+            is_initializer: false,
+        })
+    }
+
+    fn lambda(&mut self) -> ParseResult {
+        let keyword = self.previous().clone();
+
+        self.consume(&TokenType::LEFTPAREN, "Expect '(' after 'fun'.")?;
+
+        let (params, body) = self.function_rest("function")?;
+
+        Ok(Expr::Function {
+            keyword,
+            params,
+            body,
+        })
+    }
+
+    // parameter list and body, shared by declarations and anonymous functions
+    fn function_rest(&mut self, kind: &str) -> Result<(Vec<Token>, Box<Stmt>), ParseError> {
         let mut params = Vec::new();
 
         if !self.check(&TokenType::RIGHTPAREN) {
             loop {
                 if params.len() >= 255 {
-                    return Err(ParseError(
-                        "Can't have more than 255 parameters.".to_string(),
-                    ));
+                    return Err(self.error(self.peek(), "Can't have more than 255 parameters."));
                 }
 
                 params.push(self.consume(&TokenType::IDENTIFIER, "Expect parameter name.")?);
@@ -241,24 +383,22 @@ impl Parser {
 
         let body = Box::new(self.block()?);
 
-        Ok(Stmt::Function(Callable::Function {
-            name,
-            params,
-            body,
-            closure: Rc::new(RefCell::new(Environment::new())),
-        }))
+        Ok((params, body))
     }
 
+    // statements up to and including the closing '}', the opening one already consumed
     fn block(&mut self) -> Result<Stmt, ParseError> {
         let mut statements = Vec::new();
 
         while !self.check(&TokenType::RIGHTBRACE) && !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
         }
 
-        self.consume(&TokenType::RIGHTBRACE, "Expect '}' after block.")?;
+        let right_brace = self.consume(&TokenType::RIGHTBRACE, "Expect '}' after block.")?;
 
-        Ok(Stmt::Block(statements))
+        Ok(Stmt::Block(statements, right_brace))
     }
 
     fn expression(&mut self) -> ParseResult {
@@ -272,14 +412,31 @@ impl Parser {
             let equals = self.previous().clone();
             let value = self.assignment()?;
 
-            if let Expr::Variable(name) = expr {
-                return Ok(Expr::Assign(name, Box::new(value)));
+            match expr {
+                Expr::Variable(name) => return Ok(Expr::Assign(name, Box::new(value))),
+                Expr::Get { object, name } => {
+                    return Ok(Expr::Set {
+                        object,
+                        name,
+                        value: Box::new(value),
+                    })
+                }
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expr::SetIndex {
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value),
+                    })
+                }
+                _ => (),
             }
 
-            return Err(ParseError(format!(
-                "[line {}] Invalid assignment target {}",
-                equals.line, value
-            )));
+            return Err(self.error(&equals, &format!("Invalid assignment target {}", value)));
         }
 
         Ok(expr)
@@ -411,6 +568,21 @@ impl Parser {
         loop {
             if self.match_types(&[TokenType::LEFTPAREN]) {
                 expr = self.finish_call(&expr)?;
+            } else if self.match_types(&[TokenType::DOT]) {
+                let name =
+                    self.consume(&TokenType::IDENTIFIER, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if self.match_types(&[TokenType::LEFTBRACKET]) {
+                let index = self.expression()?;
+                let bracket = self.consume(&TokenType::RIGHTBRACKET, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
@@ -425,7 +597,7 @@ impl Parser {
         if !self.check(&TokenType::RIGHTPAREN) {
             loop {
                 if arguments.len() >= 255 {
-                    return Err(ParseError("Can't have more than 255 arguments".to_string()));
+                    return Err(self.error(self.peek(), "Can't have more than 255 arguments."));
                 }
                 arguments.push(Box::new(self.expression()?));
 
@@ -468,10 +640,39 @@ impl Parser {
             }
         }
 
+        if self.match_types(&[TokenType::INTERPOLATION]) {
+            return self.interpolation();
+        }
+
+        if self.match_types(&[TokenType::SUPER]) {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::DOT, "Expect '.' after 'super'.")?;
+            let method = self.consume(&TokenType::IDENTIFIER, "Expect superclass method name.")?;
+
+            return Ok(Expr::Super { keyword, method });
+        }
+
+        if self.match_types(&[TokenType::THIS]) {
+            return Ok(Expr::This(self.previous().clone()));
+        }
+
         if self.match_types(&[TokenType::IDENTIFIER]) {
             return Ok(Expr::Variable(self.previous().clone()));
         }
 
+        if self.match_types(&[TokenType::FUN]) {
+            return self.lambda();
+        }
+
+        if self.match_types(&[TokenType::LEFTBRACKET]) {
+            return self.list();
+        }
+
+        // in expression position a brace can only open a map; blocks are statements
+        if self.match_types(&[TokenType::LEFTBRACE]) {
+            return self.map();
+        }
+
         if self.match_types(&[TokenType::LEFTPAREN]) {
             let expr = self.expression()?;
             self.consume(&TokenType::RIGHTPAREN, "Expect ')' after expression.")?;
@@ -480,10 +681,86 @@ impl Parser {
 
         let peeked = self.peek();
 
-        Err(ParseError(format!(
-            "[line {}] Parse Error: Expected valid primary expression. Received '{}'.",
-            peeked.line, peeked.lexeme
-        )))
+        Err(ParseError {
+            message: format!(
+                "[line {}] Parse Error: Expected valid primary expression. Received '{}'.",
+                peeked.line, peeked.lexeme
+            ),
+            line: peeked.line,
+            span: peeked.span,
+        })
+    }
+
+    // INTERPOLATION (expression INTERPOLATION)* expression STRING
+    fn interpolation(&mut self) -> ParseResult {
+        let mut parts = Vec::new();
+
+        loop {
+            if let Some(TokenLiteral::String(s)) = &self.previous().literal {
+                if !s.is_empty() {
+                    parts.push(Expr::String(s.clone()));
+                }
+            }
+
+            parts.push(self.expression()?);
+
+            if !self.match_types(&[TokenType::INTERPOLATION]) {
+                break;
+            }
+        }
+
+        let tail = self.consume(
+            &TokenType::STRING,
+            "Expect '}' after interpolated expression.",
+        )?;
+        if let Some(TokenLiteral::String(s)) = tail.literal {
+            if !s.is_empty() {
+                parts.push(Expr::String(s));
+            }
+        }
+
+        Ok(Expr::Interpolation(parts))
+    }
+
+    fn list(&mut self) -> ParseResult {
+        let bracket = self.previous().clone();
+        let mut elements = Vec::new();
+
+        if !self.check(&TokenType::RIGHTBRACKET) {
+            loop {
+                elements.push(self.expression()?);
+
+                if !self.match_types(&[TokenType::COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(&TokenType::RIGHTBRACKET, "Expect ']' after list elements.")?;
+
+        Ok(Expr::List { bracket, elements })
+    }
+
+    fn map(&mut self) -> ParseResult {
+        let brace = self.previous().clone();
+        let mut entries = Vec::new();
+
+        if !self.check(&TokenType::RIGHTBRACE) {
+            loop {
+                let key = self.expression()?;
+                self.consume(&TokenType::COLON, "Expect ':' after map key.")?;
+                let value = self.expression()?;
+                entries.push((key, value));
+
+                if !self.match_types(&[TokenType::COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(&TokenType::RIGHTBRACE, "Expect '}' after map entries.")?;
+
+        Ok(Expr::Map { brace, entries })
     }
 
     fn match_types(&mut self, token_types: &[TokenType]) -> bool {
@@ -499,9 +776,25 @@ impl Parser {
 
     fn consume(&mut self, token_type: &TokenType, message: &str) -> Result<Token, ParseError> {
         if self.check(token_type) {
-            return Ok(self.advance().clone());
+            Ok(self.advance().clone())
         } else {
-            Err(ParseError(message.to_string()))
+            Err(self.error(self.peek(), message))
+        }
+    }
+
+    fn error(&self, token: &Token, message: &str) -> ParseError {
+        let location = match token.token_type {
+            TokenType::EOF => "at end".to_string(),
+            _ => format!("at '{}'", token.lexeme),
+        };
+
+        ParseError {
+            message: format!(
+                "[line {}] Parse Error {}: {}",
+                token.line, location, message
+            ),
+            line: token.line,
+            span: token.span,
         }
     }
 
@@ -513,6 +806,17 @@ impl Parser {
         &self.peek().token_type == token_type
     }
 
+    // `import` and `as` are only keywords where an import can go, and names everywhere else
+    fn check_keyword(&self, keyword: &str) -> bool {
+        self.check(&TokenType::IDENTIFIER) && self.peek().lexeme == keyword
+    }
+
+    fn check_next(&self, token_type: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| &token.token_type == token_type)
+    }
+
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -549,7 +853,9 @@ impl Parser {
                 | TokenType::IF
                 | TokenType::WHILE
                 | TokenType::PRINT
-                | TokenType::RETURN => return,
+                | TokenType::RETURN
+                | TokenType::THROW
+                | TokenType::TRY => return,
                 _ => (),
             }
 
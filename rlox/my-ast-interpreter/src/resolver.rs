@@ -1,4 +1,4 @@
-use crate::{Callable, Expr, Interpreter, Stmt, Token};
+use crate::{Callable, Expr, Interpreter, Local, Span, Stmt, Token, TokenType};
 use std::collections::HashMap;
 
 #[derive(Debug, thiserror::Error)]
@@ -6,19 +6,62 @@ use std::collections::HashMap;
 pub struct BindingError {
     token: String,
     message: &'static str,
-    line: usize,
+    pub line: usize,
+    pub span: Span,
+}
+
+// suspicious but valid code: reported without stopping the program from running
+#[derive(Debug, thiserror::Error)]
+#[error("[line {line}] Warning: {message}")]
+pub struct Warning {
+    pub message: String,
+    pub line: usize,
+    pub span: Span,
+}
+
+// a variable as written in the source, linked to the name it was declared with; declarations
+// are references to themselves
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub span: Span,
+    pub declaration: Option<Span>, // `None` for builtins and globals that are never declared
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum FunctionType {
     None,
     Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+// a name declared in a local scope; `slot` is its index in the matching runtime environment
+#[derive(Debug, Copy, Clone)]
+struct Variable {
+    slot: usize,
+    defined: bool,
+    read: bool,
+    line: usize,
+    span: Span,
 }
 
 pub struct Resolver<'a> {
     pub interpreter: &'a mut Interpreter,
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Variable>>,
     current_function: FunctionType,
+    current_class: ClassType,
+    loop_depth: usize,
+    warnings: Vec<Warning>,
+    references: Vec<Reference>,
+    globals: HashMap<String, Span>, // where each global was first declared
 }
 
 impl<'a> Resolver<'a> {
@@ -27,7 +70,83 @@ impl<'a> Resolver<'a> {
             interpreter,
             scopes: vec![],
             current_function: FunctionType::None,
+            current_class: ClassType::None,
+            loop_depth: 0,
+            warnings: vec![],
+            references: vec![],
+            globals: HashMap::new(),
+        }
+    }
+
+    // resolves code as if nested in scopes that already exist at runtime, given outermost first
+    // with the names of their locals in slot order
+    pub(crate) fn in_scopes(interpreter: &'a mut Interpreter, scopes: Vec<Vec<String>>) -> Self {
+        let mut resolver = Resolver::new(interpreter);
+
+        for names in scopes {
+            if names.iter().any(|name| name == "super") {
+                resolver.current_class = ClassType::Subclass;
+            } else if names.iter().any(|name| name == "this")
+                && resolver.current_class == ClassType::None
+            {
+                resolver.current_class = ClassType::Class;
+            }
+
+            let scope = names
+                .into_iter()
+                .enumerate()
+                .map(|(slot, name)| {
+                    let variable = Variable {
+                        slot,
+                        defined: true,
+                        read: true,
+                        line: 0,
+                        span: Span::default(),
+                    };
+                    (name, variable)
+                })
+                .collect();
+            resolver.scopes.push(scope);
         }
+
+        resolver
+    }
+
+    /// Warnings found so far, in source order.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        let mut warnings = std::mem::take(&mut self.warnings);
+        warnings.sort_by_key(|warning| warning.span.offset);
+        warnings
+    }
+
+    /// Every variable read, assigned or declared so far, in the order they were resolved.
+    pub fn take_references(&mut self) -> Vec<Reference> {
+        let mut references = std::mem::take(&mut self.references);
+
+        // globals can be used before they're declared, so they're only linked up once it's all read
+        for reference in &mut references {
+            if reference.declaration.is_none() {
+                reference.declaration = self.globals.get(&reference.name).copied();
+            }
+        }
+
+        references
+    }
+
+    fn reference(&mut self, token: &Token, declaration: Option<Span>) {
+        self.references.push(Reference {
+            name: token.lexeme.clone(),
+            span: token.span,
+            declaration,
+        });
+    }
+
+    fn warn(&mut self, message: String, token: &Token) {
+        self.warnings.push(Warning {
+            message,
+            line: token.line,
+            span: token.span,
+        });
     }
 
     fn begin_scope(&mut self) {
@@ -35,11 +154,29 @@ impl<'a> Resolver<'a> {
     }
 
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        let scope = self
+            .scopes
+            .pop()
+            .expect("scopes should have `Some(scope)` to end");
+
+        // a leading underscore marks a local as unused on purpose
+        for (name, variable) in scope {
+            if !variable.read && !name.starts_with('_') {
+                self.warnings.push(Warning {
+                    message: format!("Local '{name}' is never read."),
+                    line: variable.line,
+                    span: variable.span,
+                });
+            }
+        }
     }
 
     fn declare(&mut self, token: &Token) -> Result<(), BindingError> {
         if self.scopes.is_empty() {
+            self.globals
+                .entry(token.lexeme.clone())
+                .or_insert(token.span);
+            self.reference(token, None);
             return Ok(());
         }
 
@@ -53,10 +190,22 @@ impl<'a> Resolver<'a> {
                 token: token.lexeme.clone(),
                 message: "Already a variable with this name in the current scope",
                 line: token.line,
+                span: token.span,
             });
         }
 
-        scope.insert(token.lexeme.clone(), false);
+        let slot = scope.len();
+        scope.insert(
+            token.lexeme.clone(),
+            Variable {
+                slot,
+                defined: false,
+                read: false,
+                line: token.line,
+                span: token.span,
+            },
+        );
+        self.reference(token, Some(token.span));
 
         Ok(())
     }
@@ -66,22 +215,102 @@ impl<'a> Resolver<'a> {
             return;
         }
 
+        if let Some(variable) = self
+            .scopes
+            .last_mut()
+            .expect("scopes should have `Some(scope)` last_mut")
+            .get_mut(&token.lexeme)
+        {
+            variable.defined = true;
+        }
+    }
+
+    // parameters and caught errors are often left unused on purpose, so they're never warned about
+    fn mark_read(&mut self, token: &Token) {
+        if let Some(variable) = self
+            .scopes
+            .last_mut()
+            .expect("scopes should have `Some(scope)` last_mut")
+            .get_mut(&token.lexeme)
+        {
+            variable.read = true;
+        }
+    }
+
+    // `this` and `super`, bound by the interpreter in an env of their own (so always slot 0)
+    fn define_implicit(&mut self, name: &str) {
         self.scopes
             .last_mut()
             .expect("scopes should have `Some(scope)` last_mut")
-            .insert(token.lexeme.clone(), true);
+            .insert(
+                name.to_string(),
+                Variable {
+                    slot: 0,
+                    defined: true,
+                    read: true,
+                    line: 0,
+                    span: Span::default(),
+                },
+            );
     }
 
-    fn resolve_local(&mut self, expr: &Expr, token: &Token) {
-        for (i, scope) in self.scopes.iter().enumerate().rev() {
-            if scope.contains_key(&token.lexeme) {
-                self.interpreter.resolve(expr, self.scopes.len() - 1 - i);
+    // assigning to a local doesn't count as reading it; returns where the local was declared,
+    // `None` meaning it's a global
+    fn resolve_local(&mut self, expr: &Expr, token: &Token, read: bool) -> Option<Span> {
+        let scopes_len = self.scopes.len();
+        for (i, scope) in self.scopes.iter_mut().enumerate().rev() {
+            if let Some(variable) = scope.get_mut(&token.lexeme) {
+                variable.read |= read;
+                let local = Local {
+                    depth: scopes_len - 1 - i,
+                    slot: variable.slot,
+                };
+                self.interpreter.resolve(expr, local);
+                return Some(variable.span);
             }
         }
+
+        None
     }
 
+    /// Binds the variables in `statements`, writing any warnings it finds to the interpreter's
+    /// diagnostics sink as well as keeping them for `take_warnings`.
     pub fn resolve(&mut self, statements: &[Stmt]) -> Result<(), BindingError> {
-        // pub fn resolve(&mut self, statements: &[Stmt]) -> Result<Interpreter, BindingError> {
+        let first = self.warnings.len();
+        let result = self.resolve_statements(statements);
+
+        let mut warnings: Vec<&Warning> = self.warnings[first..].iter().collect();
+        warnings.sort_by_key(|warning| warning.span.offset);
+        for warning in warnings {
+            self.interpreter.report(warning);
+        }
+
+        result
+    }
+
+    fn resolve_statements(&mut self, statements: &[Stmt]) -> Result<(), BindingError> {
+        let jump = statements.iter().position(|stmt| {
+            matches!(
+                stmt,
+                Stmt::Return(..) | Stmt::Throw(..) | Stmt::Break(_) | Stmt::Continue(_)
+            )
+        });
+
+        // reported at the jump itself: statements like `print 1;` carry no token to point at
+        if let Some(idx) = jump.filter(|&idx| idx + 1 < statements.len()) {
+            let keyword = match &statements[idx] {
+                Stmt::Return(keyword, _)
+                | Stmt::Throw(keyword, _)
+                | Stmt::Break(keyword)
+                | Stmt::Continue(keyword) => keyword,
+                _ => unreachable!("position only matches jumps"),
+            };
+            self.warn(
+                format!("Code after '{}' is unreachable.", keyword.lexeme),
+                keyword,
+            );
+        }
+
         for stmt in statements {
             self.resolve_stmt(stmt)?
         }
@@ -91,16 +320,17 @@ impl<'a> Resolver<'a> {
 
     fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<(), BindingError> {
         match stmt {
-            Stmt::Block(stmts) => {
+            Stmt::Block(stmts, _) => {
                 self.begin_scope();
-                self.resolve(stmts)?;
+                self.resolve_statements(stmts)?;
                 self.end_scope();
             }
-            Stmt::Expression(expr) => self.resolve_expr(expr)?,
+            Stmt::Expression(expr, _) => self.resolve_expr(expr)?,
             Stmt::If {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.resolve_expr(condition)?;
                 self.resolve_stmt(then_branch)?;
@@ -108,32 +338,130 @@ impl<'a> Resolver<'a> {
                     self.resolve_stmt(else_branch)?;
                 }
             }
-            Stmt::Print(expr) => self.resolve_expr(expr)?,
+            Stmt::Print(_, expr) => self.resolve_expr(expr)?,
             Stmt::Return(keyword, val) => {
                 if self.current_function == FunctionType::None {
                     return Err(BindingError {
                         token: String::default(),
                         message: "Can't return from top-level code.",
                         line: keyword.line,
+                        span: keyword.span,
                     });
                 }
 
                 if let Some(val) = val {
+                    if self.current_function == FunctionType::Initializer {
+                        return Err(BindingError {
+                            token: keyword.lexeme.clone(),
+                            message: "Can't return a value from an initializer.",
+                            line: keyword.line,
+                            span: keyword.span,
+                        });
+                    }
+
                     self.resolve_expr(val)?;
                 }
             }
 
             Stmt::Var(token, initializer) => {
                 self.declare(token)?;
-                if initializer == &Expr::Nil {
+                if initializer != &Expr::Nil {
                     self.resolve_expr(initializer)?;
                 }
 
                 self.define(token);
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition, body, ..
+            } => {
                 self.resolve_expr(condition)?;
+
+                self.loop_depth += 1;
+                self.resolve_stmt(body)?;
+                self.loop_depth -= 1;
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                // the initializer's variable is scoped to the loop
+                if let Some(initializer) = initializer {
+                    self.begin_scope();
+                    self.resolve_stmt(initializer)?;
+                }
+                if let Some(condition) = condition {
+                    self.resolve_expr(condition)?;
+                }
+
+                self.loop_depth += 1;
                 self.resolve_stmt(body)?;
+                self.loop_depth -= 1;
+
+                if let Some(increment) = increment {
+                    self.resolve_expr(increment)?;
+                }
+                if initializer.is_some() {
+                    self.end_scope();
+                }
+            }
+            Stmt::Throw(_, value) => self.resolve_expr(value)?,
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.resolve_stmt(body)?;
+
+                // handler runs in one scope holding the caught error, like a function body
+                if let Some((name, handler)) = catch {
+                    let Stmt::Block(handler, _) = handler.as_ref() else {
+                        unreachable!("`catch` handlers are parsed as blocks")
+                    };
+                    self.begin_scope();
+                    self.declare(name)?;
+                    self.define(name);
+                    self.mark_read(name);
+                    self.resolve_statements(handler)?;
+                    self.end_scope();
+                }
+
+                if let Some(finally) = finally {
+                    self.resolve_stmt(finally)?;
+                }
+            }
+            Stmt::Import { keyword, name, .. } => {
+                // unnamespaced imports add globals the resolver can't see, so keep them top-level
+                if !self.scopes.is_empty() {
+                    return Err(BindingError {
+                        token: keyword.lexeme.clone(),
+                        message: "Can't import inside a block or function.",
+                        line: keyword.line,
+                        span: keyword.span,
+                    });
+                }
+
+                if let Some(name) = name {
+                    self.declare(name)?;
+                }
+            }
+            Stmt::Break(keyword) | Stmt::Continue(keyword) => {
+                if self.loop_depth == 0 {
+                    let message = match keyword.token_type {
+                        TokenType::BREAK => "Can't use 'break' outside of a loop.",
+                        _ => "Can't use 'continue' outside of a loop.",
+                    };
+
+                    return Err(BindingError {
+                        token: keyword.lexeme.clone(),
+                        message,
+                        line: keyword.line,
+                        span: keyword.span,
+                    });
+                }
             }
             Stmt::Function(callable) => match callable {
                 Callable::Function { name, .. } => {
@@ -142,7 +470,61 @@ impl<'a> Resolver<'a> {
 
                     self.resolve_function_stmt(callable, FunctionType::Function)?;
                 }
+                _ => unreachable!("function statements only hold `Callable::Function`"),
             },
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+
+                self.declare(name)?;
+                self.define(name);
+
+                if let Some(superclass) = superclass {
+                    if let Expr::Variable(superclass_name) = superclass {
+                        if superclass_name.lexeme == name.lexeme {
+                            return Err(BindingError {
+                                token: superclass_name.lexeme.clone(),
+                                message: "A class can't inherit from itself.",
+                                line: superclass_name.line,
+                                span: superclass_name.span,
+                            });
+                        }
+                    }
+
+                    self.current_class = ClassType::Subclass;
+                    self.resolve_expr(superclass)?;
+
+                    self.begin_scope();
+                    self.define_implicit("super");
+                }
+
+                self.begin_scope();
+                self.define_implicit("this");
+
+                for method in methods {
+                    let declaration = match method {
+                        Callable::Function { name, .. } if name.lexeme == "init" => {
+                            FunctionType::Initializer
+                        }
+                        _ => FunctionType::Method,
+                    };
+
+                    self.resolve_function_stmt(method, declaration)?;
+                }
+
+                self.end_scope();
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+
+                self.current_class = enclosing_class;
+            }
         }
 
         Ok(())
@@ -152,25 +534,61 @@ impl<'a> Resolver<'a> {
         &mut self,
         function: &Callable,
         fn_type: FunctionType,
+    ) -> Result<(), BindingError> {
+        match function {
+            Callable::Function { params, body, .. } => self.resolve_function(params, body, fn_type),
+            _ => unreachable!("only `Callable::Function` bodies need resolving"),
+        }
+    }
+
+    fn resolve_function(
+        &mut self,
+        params: &[Token],
+        body: &Stmt,
+        fn_type: FunctionType,
     ) -> Result<(), BindingError> {
         let enclosing_fn = self.current_function;
         self.current_function = fn_type;
 
+        // loops don't extend into function bodies: `break` can't jump out of a call
+        let enclosing_loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+
+        // globals aren't in `scopes`, but the ones declared so far are in `globals`
+        for param in params {
+            if self.globals.contains_key(&param.lexeme)
+                || self
+                    .scopes
+                    .iter()
+                    .any(|scope| scope.contains_key(&param.lexeme))
+            {
+                self.warn(
+                    format!(
+                        "Parameter '{}' shadows a variable in an enclosing scope.",
+                        param.lexeme
+                    ),
+                    param,
+                );
+            }
+        }
+
         self.begin_scope();
 
-        match function {
-            Callable::Function { params, body, .. } => {
-                for param in params {
-                    self.declare(param)?;
-                    self.define(param);
-                }
+        for param in params {
+            self.declare(param)?;
+            self.define(param);
+            self.mark_read(param);
+        }
 
-                self.resolve_stmt(body)?;
-            }
+        // body shares the params scope (matches `Callable::call`'s single call env)
+        match body {
+            Stmt::Block(stmts, _) => self.resolve_statements(stmts)?,
+            stmt => self.resolve_stmt(stmt)?,
         }
 
         self.end_scope();
         self.current_function = enclosing_fn;
+        self.loop_depth = enclosing_loop_depth;
 
         Ok(())
     }
@@ -178,8 +596,9 @@ impl<'a> Resolver<'a> {
     fn resolve_expr(&mut self, expr: &Expr) -> Result<(), BindingError> {
         match expr {
             Expr::Variable(token) => {
+                let mut declaration = None;
                 if !self.scopes.is_empty() {
-                    if let Some(&false) = self
+                    if let Some(Variable { defined: false, .. }) = self
                         .scopes
                         .last()
                         .expect("scopes should have `Some(scope)` re: last()")
@@ -189,15 +608,23 @@ impl<'a> Resolver<'a> {
                             token: token.lexeme.clone(),
                             message: "Can't read local variable in its own initializer",
                             line: token.line,
+                            span: token.span,
                         });
                     }
 
-                    self.resolve_local(expr, token);
+                    declaration = self.resolve_local(expr, token, true);
                 }
+
+                self.reference(token, declaration);
             }
             Expr::Assign(name, val) => {
+                if matches!(val.as_ref(), Expr::Variable(value) if value.lexeme == name.lexeme) {
+                    self.warn(format!("'{}' is assigned to itself.", name.lexeme), name);
+                }
+
                 self.resolve_expr(val)?;
-                self.resolve_local(expr, name);
+                let declaration = self.resolve_local(expr, name, false);
+                self.reference(name, declaration);
             }
             Expr::Unary { right, .. } => self.resolve_expr(right)?,
             Expr::Binary { left, right, .. } => {
@@ -217,6 +644,79 @@ impl<'a> Resolver<'a> {
                 self.resolve_expr(left)?;
                 self.resolve_expr(right)?;
             }
+            Expr::Get { object, .. } => self.resolve_expr(object)?,
+            Expr::Function { params, body, .. } => {
+                self.resolve_function(params, body, FunctionType::Function)?
+            }
+            Expr::Interpolation(parts) => {
+                for part in parts {
+                    self.resolve_expr(part)?;
+                }
+            }
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    self.resolve_expr(element)?;
+                }
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.resolve_expr(key)?;
+                    self.resolve_expr(value)?;
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)?;
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)?;
+            }
+            Expr::Set { object, value, .. } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+            }
+            Expr::This(keyword) => {
+                if self.current_class == ClassType::None {
+                    return Err(BindingError {
+                        token: keyword.lexeme.clone(),
+                        message: "Can't use 'this' outside of a class.",
+                        line: keyword.line,
+                        span: keyword.span,
+                    });
+                }
+
+                self.resolve_local(expr, keyword, true);
+            }
+            Expr::Super { keyword, .. } => {
+                match self.current_class {
+                    ClassType::None => {
+                        return Err(BindingError {
+                            token: keyword.lexeme.clone(),
+                            message: "Can't use 'super' outside of a class.",
+                            line: keyword.line,
+                            span: keyword.span,
+                        })
+                    }
+                    ClassType::Class => {
+                        return Err(BindingError {
+                            token: keyword.lexeme.clone(),
+                            message: "Can't use 'super' in a class with no superclass.",
+                            line: keyword.line,
+                            span: keyword.span,
+                        })
+                    }
+                    ClassType::Subclass => (),
+                }
+
+                self.resolve_local(expr, keyword, true);
+            }
             _ => return Ok(()), // literals don't need binding resolution
         }
 
@@ -1,30 +1,70 @@
-use crate::{Token, TokenLiteral, TokenType};
+use crate::{Comment, Span, Token, TokenLiteral, TokenType};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// 0 is left for spans that don't come from any source (`Span::default()`)
+static NEXT_SOURCE_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, thiserror::Error)]
+#[error("[line {line}] Error: {message}")]
+pub struct ScanError {
+    pub message: String,
+    pub line: usize,
+    pub span: Span,
+}
 
 pub struct Scanner {
     pub source: Vec<char>,
     pub tokens: Vec<Token>,
-    pub errors: Vec<String>,
+    pub errors: Vec<ScanError>,
+    pub comments: Vec<Comment>,
     keywords: HashMap<&'static str, TokenType>,
+    source_id: usize, // unique to this scanner, stamped on every span it produces
+    byte_offsets: Vec<usize>, // byte offset of each char in `source`, plus one past the end
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize, // char idx where the current line begins
+    start_line: usize,
+    start_column: usize,
+    interpolations: Vec<Interpolation>, // `${` segments still waiting for their closing '}'
+}
+
+// an open `${`: where it started, and how many '{' inside it are still unclosed
+struct Interpolation {
+    depth: usize,
+    line: usize,
+    span: Span,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Self {
+        let mut byte_offsets: Vec<usize> = source.char_indices().map(|(idx, _)| idx).collect();
+        byte_offsets.push(source.len());
+
         Scanner {
             source: source.chars().collect(),
             tokens: vec![],
             errors: vec![],
+            comments: vec![],
+            byte_offsets,
+            source_id: NEXT_SOURCE_ID.fetch_add(1, Ordering::Relaxed),
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: vec![],
             keywords: HashMap::from([
                 ("and", TokenType::AND),
+                ("break", TokenType::BREAK),
+                ("catch", TokenType::CATCH),
                 ("class", TokenType::CLASS),
+                ("continue", TokenType::CONTINUE),
                 ("else", TokenType::ELSE),
                 ("false", TokenType::FALSE),
+                ("finally", TokenType::FINALLY),
                 ("for", TokenType::FOR),
                 ("fun", TokenType::FUN),
                 ("if", TokenType::IF),
@@ -34,23 +74,54 @@ impl Scanner {
                 ("return", TokenType::RETURN),
                 ("super", TokenType::SUPER),
                 ("this", TokenType::THIS),
+                ("throw", TokenType::THROW),
                 ("true", TokenType::TRUE),
+                ("try", TokenType::TRY),
                 ("var", TokenType::VAR),
                 ("while", TokenType::WHILE),
             ]),
         }
     }
 
-    pub fn scan_tokens(mut self) -> (Vec<Token>, Vec<String>) {
+    /// The `source` of every span this scanner produces.
+    pub fn source_id(&self) -> usize {
+        self.source_id
+    }
+
+    pub fn scan_tokens(self) -> (Vec<Token>, Vec<ScanError>) {
+        let (tokens, _, errors) = self.scan_with_comments();
+        (tokens, errors)
+    }
+
+    /// Like `scan_tokens`, also giving back the comments it skipped, in order.
+    pub fn scan_with_comments(mut self) -> (Vec<Token>, Vec<Comment>, Vec<ScanError>) {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.start - self.line_start + 1;
             self.scan_token()
         }
 
-        self.tokens
-            .push(Token::new(TokenType::EOF, "".to_string(), None, self.line));
+        for interpolation in std::mem::take(&mut self.interpolations) {
+            self.errors.push(ScanError {
+                message: "Unterminated string interpolation.".to_string(),
+                line: interpolation.line,
+                span: interpolation.span,
+            });
+        }
+
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.start - self.line_start + 1;
+        self.tokens.push(Token::new(
+            TokenType::EOF,
+            "".to_string(),
+            None,
+            self.line,
+            self.span(),
+        ));
 
-        (self.tokens, self.errors)
+        (self.tokens, self.comments, self.errors)
     }
 
     pub fn scan_token(&mut self) {
@@ -58,9 +129,28 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LEFTPAREN, None),
             ')' => self.add_token(TokenType::RIGHTPAREN, None),
-            '{' => self.add_token(TokenType::LEFTBRACE, None),
-            '}' => self.add_token(TokenType::RIGHTBRACE, None),
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
+                }
+                self.add_token(TokenType::LEFTBRACE, None)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // closes `${`: the rest of the string literal picks up from here
+                Some(interpolation) if interpolation.depth == 0 => {
+                    self.interpolations.pop();
+                    self.string();
+                }
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    self.add_token(TokenType::RIGHTBRACE, None)
+                }
+                None => self.add_token(TokenType::RIGHTBRACE, None),
+            },
+            '[' => self.add_token(TokenType::LEFTBRACKET, None),
+            ']' => self.add_token(TokenType::RIGHTBRACKET, None),
             ',' => self.add_token(TokenType::COMMA, None),
+            ':' => self.add_token(TokenType::COLON, None),
             '.' => self.add_token(TokenType::DOT, None),
             '-' => self.add_token(TokenType::MINUS, None),
             '+' => self.add_token(TokenType::PLUS, None),
@@ -89,12 +179,22 @@ impl Scanner {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+
+                    let text: String = self.source[self.start..self.current].iter().collect();
+                    let trailing = self.source[self.line_start..self.start]
+                        .iter()
+                        .any(|c| !c.is_whitespace());
+                    self.comments.push(Comment {
+                        text: text.trim_end().to_string(),
+                        line: self.line,
+                        trailing,
+                    });
                 } else {
                     self.add_token(TokenType::SLASH, None)
                 }
             }
             ' ' | '\r' | '\t' => (),
-            '\n' => self.line += 1,
+            '\n' => self.newline(),
 
             '"' => self.string(),
 
@@ -105,10 +205,7 @@ impl Scanner {
                     self.identifier();
                 } else {
                     // unknown char
-                    self.errors.push(format!(
-                        "[line {}] Error: Unexpected character: {}",
-                        self.line, c
-                    ))
+                    self.error(format!("Unexpected character: {}", c))
                 }
             }
         }
@@ -152,24 +249,40 @@ impl Scanner {
         if let Ok(num) = num_str.parse::<f64>() {
             self.add_token(TokenType::NUMBER, Some(TokenLiteral::Number(num)));
         } else {
-            self.errors.push(format!(
-                "[line {}] Error: Invalid number literal: {}",
-                self.line, num_str
-            ))
+            self.error(format!("Invalid number literal: {}", num_str))
         }
     }
 
+    // scans a string segment starting after an opening '"' or an interpolation's closing '}'.
+    // `"a ${b} c"` becomes INTERPOLATION("a "), IDENTIFIER(b), STRING(" c")
     fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.advance();
+                self.advance();
+
+                let literal = self.source[self.start + 1..self.current - 2]
+                    .iter()
+                    .collect::<String>();
+                self.add_token(
+                    TokenType::INTERPOLATION,
+                    Some(TokenLiteral::String(literal)),
+                );
+                self.interpolations.push(Interpolation {
+                    depth: 0,
+                    line: self.line,
+                    span: self.span(),
+                });
+                return;
+            }
+
+            if self.advance() == '\n' {
+                self.newline();
             }
-            self.advance();
         }
 
         if self.is_at_end() {
-            self.errors
-                .push(format!("[line {}] Error: Unterminated string.", self.line));
+            self.error("Unterminated string.".to_string());
             return;
         }
 
@@ -221,8 +334,37 @@ impl Scanner {
         let text = self.source[self.start..self.current]
             .iter()
             .collect::<String>();
-        self.tokens
-            .push(Token::new(token_type, text, literal, self.line))
+        self.tokens.push(Token::new(
+            token_type,
+            text,
+            literal,
+            self.start_line,
+            self.span(),
+        ))
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(ScanError {
+            message,
+            line: self.start_line,
+            span: self.span(),
+        })
+    }
+
+    // span of the lexeme currently being scanned
+    fn span(&self) -> Span {
+        let offset = self.byte_offsets[self.start];
+        Span::new(
+            offset,
+            self.byte_offsets[self.current] - offset,
+            self.start_column,
+            self.source_id,
+        )
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
     }
 
     pub fn is_at_end(&self) -> bool {
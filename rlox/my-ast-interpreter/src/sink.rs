@@ -0,0 +1,62 @@
+use crate::RuntimeError;
+use std::{
+    cell::RefCell,
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Destination for text the interpreter emits: program output (`print`) or diagnostics.
+/// Clones share the same writer, so a sink handed to the interpreter can still be read from.
+#[derive(Clone)]
+pub struct Sink(Rc<RefCell<dyn Write>>);
+
+impl Sink {
+    pub fn new(writer: impl Write + 'static) -> Self {
+        Sink(Rc::new(RefCell::new(writer)))
+    }
+
+    pub fn stdout() -> Self {
+        Sink::new(io::stdout())
+    }
+
+    pub fn stderr() -> Self {
+        Sink::new(io::stderr())
+    }
+
+    /// Calls `callback` with each line written, trailing newline included.
+    pub fn from_fn(callback: impl FnMut(&str) + 'static) -> Self {
+        Sink::new(Callback(callback))
+    }
+
+    pub fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut writer = self.0.borrow_mut();
+        writer.write_all(format!("{line}\n").as_bytes())?;
+        writer.flush()
+    }
+
+    // for the interpreter's own writes, where a broken sink is reported like any other failure
+    pub(crate) fn emit(&self, line: &str) -> Result<(), RuntimeError> {
+        self.write_line(line)
+            .map_err(|err| RuntimeError::Native(format!("Failed to write output: {err}.")))
+    }
+}
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sink")
+    }
+}
+
+struct Callback<F>(F);
+
+impl<F: FnMut(&str)> Write for Callback<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (self.0)(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -4,29 +4,83 @@ use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
-    Expression(Expr),
-    Print(Expr),
-    Var(Token, Expr), // keyword, value
-    Block(Vec<Stmt>),
+    Expression(Expr, Token), // value, last token (the `;` unless it was left off)
+    Print(Token, Expr),      // keyword, value
+    Var(Token, Expr),        // keyword, value
+    Block(Vec<Stmt>, Token), // statements, closing brace
     If {
+        keyword: Token,
         condition: Expr,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
     While {
+        keyword: Token,
         condition: Expr,
         body: Box<Stmt>,
     },
+    For {
+        keyword: Token,
+        initializer: Option<Box<Stmt>>,
+        condition: Option<Expr>, // a missing condition loops forever
+        increment: Option<Expr>,
+        body: Box<Stmt>,
+    },
     Function(Callable),
+    Class {
+        name: Token,
+        superclass: Option<Expr>, // `Expr::Variable` naming the superclass
+        methods: Vec<Callable>,
+        right_brace: Token,
+    },
+    Import {
+        keyword: Token,
+        path: String,
+        name: Option<Token>, // namespace from `as name`, otherwise declarations are copied in
+    },
     Return(Token, Option<Expr>), // keyword, value
+    Throw(Token, Expr),          // keyword, value
+    // each part is a `Block`
+    Try {
+        keyword: Token,
+        body: Box<Stmt>,
+        catch: Option<(Token, Box<Stmt>)>, // bound name, handler
+        finally: Option<Box<Stmt>>,
+    },
+    Break(Token),
+    Continue(Token),
+}
+
+impl Stmt {
+    /// Line the statement starts on, `None` for blocks (only the statements in them have a line).
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            // a literal has no token of its own, so fall back to the end of the statement
+            Stmt::Expression(expr, end) => expr.line().or(Some(end.line)),
+            Stmt::Function(Callable::Function { name, .. }) => Some(name.line),
+            Stmt::Function(_) => None,
+            Stmt::Var(name, _) | Stmt::Class { name, .. } => Some(name.line),
+            Stmt::Print(keyword, _)
+            | Stmt::Import { keyword, .. }
+            | Stmt::If { keyword, .. }
+            | Stmt::While { keyword, .. }
+            | Stmt::For { keyword, .. }
+            | Stmt::Try { keyword, .. }
+            | Stmt::Return(keyword, _)
+            | Stmt::Throw(keyword, _)
+            | Stmt::Break(keyword)
+            | Stmt::Continue(keyword) => Some(keyword.line),
+            Stmt::Block(..) => None,
+        }
+    }
 }
 
 impl Display for Stmt {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Stmt::Var(tok, expr) => write!(f, "{} = {}", tok.lexeme, expr),
-            Stmt::Print(expr) | Stmt::Expression(expr) => write!(f, "{}", expr),
-            Stmt::Block(statements) => {
+            Stmt::Print(_, expr) | Stmt::Expression(expr, _) => write!(f, "{}", expr),
+            Stmt::Block(statements, _) => {
                 let stmts = statements
                     .iter()
                     .map(|stmt| format!("{}", stmt))
@@ -39,6 +93,7 @@ impl Display for Stmt {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 writeln!(f, "if ({}) {{", condition)?;
                 writeln!(f, "{}", then_branch)?;
@@ -50,11 +105,36 @@ impl Display for Stmt {
 
                 writeln!(f, "}}")
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition, body, ..
+            } => {
                 writeln!(f, "while ({}) {{", condition)?;
                 writeln!(f, "{}", body)?;
                 writeln!(f, "}}")
             }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                write!(f, "for (")?;
+                if let Some(initializer) = initializer {
+                    write!(f, "{}", initializer)?;
+                }
+                write!(f, ";")?;
+                if let Some(condition) = condition {
+                    write!(f, " {}", condition)?;
+                }
+                write!(f, ";")?;
+                if let Some(increment) = increment {
+                    write!(f, " {}", increment)?;
+                }
+                writeln!(f, ") {{")?;
+                writeln!(f, "{}", body)?;
+                writeln!(f, "}}")
+            }
             Stmt::Function(callable) => match callable {
                 Callable::Function {
                     name, params, body, ..
@@ -71,7 +151,24 @@ impl Display for Stmt {
                     write!(f, "{}", body)?;
                     write!(f, "}}")
                 }
+                _ => write!(f, "{}", callable),
             },
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                write!(f, "class {}", name.lexeme)?;
+                if let Some(superclass) = superclass {
+                    write!(f, " < {}", superclass)?;
+                }
+                writeln!(f, " {{")?;
+                for method in methods {
+                    writeln!(f, "{}", Stmt::Function(method.clone()))?;
+                }
+                write!(f, "}}")
+            }
             Stmt::Return(_, val) => {
                 if let Some(expr) = val {
                     write!(f, "{}", expr)
@@ -79,6 +176,31 @@ impl Display for Stmt {
                     write!(f, "nil")
                 }
             }
+            Stmt::Import { path, name, .. } => match name {
+                Some(name) => write!(f, "import \"{}\" as {}", path, name.lexeme),
+                None => write!(f, "import \"{}\"", path),
+            },
+            Stmt::Throw(_, value) => write!(f, "throw {}", value),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                writeln!(f, "try {{")?;
+                writeln!(f, "{}", body)?;
+                if let Some((name, handler)) = catch {
+                    writeln!(f, "}} catch ({}) {{", name.lexeme)?;
+                    writeln!(f, "{}", handler)?;
+                }
+                if let Some(finally) = finally {
+                    writeln!(f, "}} finally {{")?;
+                    writeln!(f, "{}", finally)?;
+                }
+                writeln!(f, "}}")
+            }
+            Stmt::Break(_) => write!(f, "break"),
+            Stmt::Continue(_) => write!(f, "continue"),
         }
     }
 }
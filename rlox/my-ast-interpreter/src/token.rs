@@ -9,8 +9,11 @@ pub enum TokenType {
     RIGHTPAREN,
     LEFTBRACE,
     RIGHTBRACE,
+    LEFTBRACKET,
+    RIGHTBRACKET,
 
     COMMA,
+    COLON,
     DOT,
     MINUS,
     PLUS,
@@ -30,14 +33,19 @@ pub enum TokenType {
     SLASH,
 
     STRING,
+    INTERPOLATION,
     NUMBER,
 
     IDENTIFIER,
 
     AND,
+    BREAK,
+    CATCH,
     CLASS,
+    CONTINUE,
     ELSE,
     FALSE,
+    FINALLY,
     FOR,
     FUN,
     IF,
@@ -47,7 +55,9 @@ pub enum TokenType {
     RETURN,
     SUPER,
     THIS,
+    THROW,
     TRUE,
+    TRY,
     VAR,
     WHILE,
 
@@ -61,7 +71,10 @@ impl fmt::Display for TokenType {
             TokenType::RIGHTPAREN => "RIGHT_PAREN",
             TokenType::LEFTBRACE => "LEFT_BRACE",
             TokenType::RIGHTBRACE => "RIGHT_BRACE",
+            TokenType::LEFTBRACKET => "LEFT_BRACKET",
+            TokenType::RIGHTBRACKET => "RIGHT_BRACKET",
             TokenType::COMMA => "COMMA",
+            TokenType::COLON => "COLON",
             TokenType::DOT => "DOT",
             TokenType::MINUS => "MINUS",
             TokenType::PLUS => "PLUS",
@@ -77,12 +90,17 @@ impl fmt::Display for TokenType {
             TokenType::GREATEREQUAL => "GREATER_EQUAL",
             TokenType::SLASH => "SLASH",
             TokenType::STRING => "STRING",
+            TokenType::INTERPOLATION => "INTERPOLATION",
             TokenType::NUMBER => "NUMBER",
             TokenType::IDENTIFIER => "IDENTIFIER",
             TokenType::AND => "AND",
+            TokenType::BREAK => "BREAK",
+            TokenType::CATCH => "CATCH",
             TokenType::CLASS => "CLASS",
+            TokenType::CONTINUE => "CONTINUE",
             TokenType::ELSE => "ELSE",
             TokenType::FALSE => "FALSE",
+            TokenType::FINALLY => "FINALLY",
             TokenType::FOR => "FOR",
             TokenType::FUN => "FUN",
             TokenType::IF => "IF",
@@ -92,7 +110,9 @@ impl fmt::Display for TokenType {
             TokenType::RETURN => "RETURN",
             TokenType::SUPER => "SUPER",
             TokenType::THIS => "THIS",
+            TokenType::THROW => "THROW",
             TokenType::TRUE => "TRUE",
+            TokenType::TRY => "TRY",
             TokenType::VAR => "VAR",
             TokenType::WHILE => "WHILE",
             TokenType::EOF => "EOF",
@@ -129,12 +149,44 @@ impl PartialEq for TokenLiteral {
 
 impl Eq for TokenLiteral {}
 
+// location of a token in its source: `offset` and `len` are in bytes, `column` is 1-based in chars.
+// `source` tells apart tokens at the same position in different scans (files, repl entries),
+// which the interpreter relies on when it keys resolved locals by expression
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+    pub column: usize,
+    pub source: usize,
+}
+
+impl Span {
+    pub fn new(offset: usize, len: usize, column: usize, source: usize) -> Self {
+        Span {
+            offset,
+            len,
+            column,
+            source,
+        }
+    }
+}
+
+/// A `//` comment. The parser never sees these; `Scanner::scan_with_comments` keeps them for the
+/// formatter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    pub text: String, // from the `//` to the end of the line
+    pub line: usize,
+    pub trailing: bool, // whether it follows code on its line
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal: Option<TokenLiteral>,
     pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
@@ -143,12 +195,14 @@ impl Token {
         lexeme: String,
         literal: Option<TokenLiteral>,
         line: usize,
+        span: Span,
     ) -> Self {
         Token {
             token_type,
             lexeme,
             literal,
             line,
+            span,
         }
     }
 }
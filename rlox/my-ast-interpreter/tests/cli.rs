@@ -0,0 +1,68 @@
+// Command line handling: flags can go before or after the file, and a file that can't be read
+// is an error.
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn rlox(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_my-ast-interpreter"))
+        .args(args)
+        .output()
+        .expect("should run rlox binary")
+}
+
+fn script(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rlox-cli-{}-{name}", std::process::id()));
+    fs::write(&path, source).expect("should write script");
+    path
+}
+
+#[test]
+fn a_missing_file_fails() {
+    for args in [
+        ["fmt", "missing.lox", "--check"],
+        ["fmt", "--check", "missing.lox"],
+    ] {
+        let output = rlox(&args);
+        assert_eq!(output.status.code(), Some(66), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("Failed to read file"));
+    }
+
+    assert_eq!(rlox(&["run"]).status.code(), Some(64));
+}
+
+#[test]
+fn flags_can_come_before_the_file() {
+    let unformatted = script("unformatted.lox", "print  1;\n");
+    let path = unformatted.to_str().expect("should be utf-8");
+    let output = rlox(&["fmt", "--check", path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{path} is not formatted\n")
+    );
+
+    let warning = script("warning.lox", "var a = 1;\na = a;\nprint a;\n");
+    let path = warning.to_str().expect("should be utf-8");
+    assert_eq!(rlox(&["run", path]).status.code(), Some(0));
+    assert_eq!(
+        rlox(&["run", "--warnings-as-errors", path]).status.code(),
+        Some(65)
+    );
+
+    let reads = script("reads.lox", "readLine();\n");
+    let path = reads.to_str().expect("should be utf-8");
+    let output = rlox(&["run", "--io=none", path]);
+    assert_eq!(output.status.code(), Some(70));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("I/O is disabled."));
+
+    let output = rlox(&["parse", "--format", "sexpr", path]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "(; (call readLine))\n"
+    );
+
+    for path in [unformatted, warning, reads] {
+        fs::remove_file(path).expect("should remove script");
+    }
+}
@@ -0,0 +1,53 @@
+// Harness shared by the embedding tests: compiling scripts into an interpreter in-process and
+// capturing what it writes. Each test crate only uses some of it.
+#![allow(dead_code)]
+
+use my_ast_interpreter::{Interpreter, Parser, Resolver, RuntimeError, Scanner, Stmt};
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+// scans, parses and resolves `source` into `interpreter`, which must all succeed
+pub fn compile(interpreter: &mut Interpreter, source: &str) -> Vec<Stmt> {
+    let scanner = Scanner::new(source.to_string());
+    interpreter.add_source(scanner.source_id(), source);
+    let (tokens, errors) = scanner.scan_tokens();
+    assert!(errors.is_empty(), "{errors:?}");
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{errors:?}");
+    Resolver::new(interpreter)
+        .resolve(&statements)
+        .expect("should resolve");
+    interpreter
+        .set_status("run")
+        .expect("should set interpreter status::run");
+
+    statements
+}
+
+pub fn try_run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let statements = compile(interpreter, source);
+    interpreter.interpret(statements)
+}
+
+pub fn run(interpreter: &mut Interpreter, source: &str) {
+    try_run(interpreter, source).expect("should run");
+}
+
+// shared buffer so the test can read what the interpreter wrote into it
+#[derive(Clone, Default)]
+pub struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl Buffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).to_string()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
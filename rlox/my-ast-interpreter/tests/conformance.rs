@@ -0,0 +1,265 @@
+// Runs `.lox` files under `tests/lox/<suite>` through `rlox run` and checks them against
+// Crafting Interpreters-style annotations written as comments in the scripts themselves:
+//
+//   print 1 + 2; // expect: 3
+//   nil + 1;     // expect runtime error: Operands must be two numbers or two strings.
+//   var a = ;    // expect compile error: Expected valid primary expression.
+//   a = a;       // expect warning: 'a' is assigned to itself.
+//   // args: --io=none
+//
+// Runtime errors must exit with 70 and compile (scan/parse/resolve) errors with 65. Compile
+// errors are reported in the order they're annotated.
+// Warnings are only checked for when annotated, and don't change the exit code.
+// Each script runs from its own empty working directory, so relative file writes stay sandboxed.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Default)]
+struct Expectations {
+    output: Vec<String>,
+    runtime_error: Option<(usize, String)>,
+    compile_errors: Vec<(usize, String)>,
+    warnings: Vec<(usize, String)>,
+    args: Vec<String>, // extra cli flags passed after the script path
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Self {
+        let mut expectations = Expectations::default();
+
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx + 1;
+
+            if let Some(expected) = annotation(line, "// expect: ") {
+                expectations.output.push(expected);
+            } else if let Some(expected) = annotation(line, "// expect runtime error: ") {
+                expectations.runtime_error = Some((line_number, expected));
+            } else if let Some(expected) = annotation(line, "// expect compile error: ") {
+                expectations.compile_errors.push((line_number, expected));
+            } else if let Some(expected) = annotation(line, "// expect warning: ") {
+                expectations.warnings.push((line_number, expected));
+            } else if let Some(args) = annotation(line, "// args: ") {
+                expectations
+                    .args
+                    .extend(args.split_whitespace().map(str::to_string));
+            }
+        }
+
+        expectations
+    }
+
+    fn exit_code(&self) -> i32 {
+        if !self.compile_errors.is_empty() {
+            65
+        } else if self.runtime_error.is_some() {
+            70
+        } else {
+            0
+        }
+    }
+}
+
+fn annotation(line: &str, marker: &str) -> Option<String> {
+    line.find(marker)
+        .map(|idx| line[idx + marker.len()..].to_string())
+}
+
+// checks a single script, returning a description of every mismatch
+fn check(path: &Path) -> Vec<String> {
+    let source = fs::read_to_string(path).expect("should read lox test file");
+    let expectations = Expectations::parse(&source);
+
+    let stem = path.file_stem().expect("should have a file name");
+    let scratch = std::env::temp_dir().join(format!(
+        "rlox-conformance-{}-{}",
+        std::process::id(),
+        stem.to_string_lossy()
+    ));
+    fs::create_dir_all(&scratch).expect("should create scratch dir");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_my-ast-interpreter"))
+        .arg("run")
+        .arg(path)
+        .args(&expectations.args)
+        .current_dir(&scratch)
+        .output()
+        .expect("should run rlox binary");
+
+    fs::remove_dir_all(&scratch).expect("should remove scratch dir");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut failures = Vec::new();
+
+    let actual_output = stdout.lines().collect::<Vec<&str>>();
+    if actual_output != expectations.output {
+        failures.push(format!(
+            "expected output {:?}, got {:?}",
+            expectations.output, actual_output
+        ));
+    }
+
+    if let Some((line, message)) = &expectations.runtime_error {
+        let expected = format!("[line {line}] Runtime Error: {message}");
+        if !stderr.lines().any(|err_line| err_line == expected) {
+            failures.push(format!("expected `{expected}` on stderr, got:\n{stderr}"));
+        }
+    }
+
+    // in the order they're annotated, each after the one before it
+    let mut err_lines = stderr.lines();
+    for (line, message) in &expectations.compile_errors {
+        let prefix = format!("[line {line}]");
+        if !err_lines.any(|err_line| err_line.starts_with(&prefix) && err_line.contains(message)) {
+            failures.push(format!(
+                "expected compile error `{prefix} ... {message}` on stderr, got:\n{stderr}"
+            ));
+            break;
+        }
+    }
+
+    for (line, message) in &expectations.warnings {
+        let expected = format!("[line {line}] Warning: {message}");
+        if !stderr.lines().any(|err_line| err_line == expected) {
+            failures.push(format!("expected `{expected}` on stderr, got:\n{stderr}"));
+        }
+    }
+
+    let expected_code = expectations.exit_code();
+    if output.status.code() != Some(expected_code) {
+        failures.push(format!(
+            "expected exit code {expected_code}, got {:?}",
+            output.status.code()
+        ));
+    }
+
+    failures
+}
+
+fn run_suite(suite: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("lox")
+        .join(suite);
+
+    let mut paths = fs::read_dir(&dir)
+        .unwrap_or_else(|_| panic!("should read test suite dir {}", dir.display()))
+        .map(|entry| entry.expect("should read dir entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    assert!(!paths.is_empty(), "no .lox files in {}", dir.display());
+
+    let failures = paths
+        .iter()
+        .flat_map(|path| {
+            check(path)
+                .into_iter()
+                .map(move |failure| format!("{}: {failure}", path.display()))
+        })
+        .collect::<Vec<String>>();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+#[test]
+fn assignment() {
+    run_suite("assignment");
+}
+
+#[test]
+fn block_scoping() {
+    run_suite("scoping");
+}
+
+#[test]
+fn closures() {
+    run_suite("closures");
+}
+
+#[test]
+fn functions_and_recursion() {
+    run_suite("functions");
+}
+
+#[test]
+fn control_flow() {
+    run_suite("control_flow");
+}
+
+#[test]
+fn logical_operators() {
+    run_suite("logical_operators");
+}
+
+#[test]
+fn operators() {
+    run_suite("operators");
+}
+
+#[test]
+fn classes() {
+    run_suite("classes");
+}
+
+#[test]
+fn inheritance() {
+    run_suite("inheritance");
+}
+
+#[test]
+fn natives() {
+    run_suite("natives");
+}
+
+#[test]
+fn lists() {
+    run_suite("lists");
+}
+
+#[test]
+fn maps() {
+    run_suite("maps");
+}
+
+#[test]
+fn anonymous_functions() {
+    run_suite("anonymous_functions");
+}
+
+#[test]
+fn string_interpolation() {
+    run_suite("interpolation");
+}
+
+#[test]
+fn parse_errors() {
+    run_suite("parse_errors");
+}
+
+#[test]
+fn modules() {
+    run_suite("modules");
+}
+
+#[test]
+fn exceptions() {
+    run_suite("exceptions");
+}
+
+#[test]
+fn stdlib() {
+    run_suite("stdlib");
+}
+
+#[test]
+fn io() {
+    run_suite("io");
+}
+
+#[test]
+fn warnings() {
+    run_suite("warnings");
+}
@@ -0,0 +1,177 @@
+// `debug`: a scripted session drives the debugger, with the program's output interleaved in
+// the transcript where it happened.
+mod common;
+
+use common::{compile, Buffer};
+use my_ast_interpreter::{Debugger, ExecutionHook, Interpreter, RuntimeError, Sink, Stmt};
+use std::cell::RefCell;
+use std::io::{self, Cursor};
+use std::rc::Rc;
+
+// the transcript, and whether the program ran to the end
+fn debug(source: &str, breakpoints: &[usize], commands: &str) -> (String, bool) {
+    let mut interpreter = Interpreter::new();
+    let statements = compile(&mut interpreter, source);
+    let transcript = Buffer::default();
+    interpreter.set_output(Sink::new(transcript.clone()));
+
+    let mut debugger = Debugger::new(
+        source,
+        Cursor::new(commands.to_string()),
+        transcript.clone(),
+    );
+    for line in breakpoints {
+        debugger.add_breakpoint(*line);
+    }
+    let debugger = Rc::new(RefCell::new(debugger));
+    interpreter.set_hook(Some(debugger.clone()));
+
+    let result = interpreter.interpret(statements);
+    assert_eq!(result.is_err(), debugger.borrow().has_quit());
+
+    (transcript.contents(), result.is_ok())
+}
+
+const PROGRAM: &str = "\
+fun add(a, b) {
+  var sum = a + b;
+  return sum;
+}
+
+var total = add(1, 2);
+{
+  var name = \"lox\";
+  print add(total, 3);
+}
+print total;
+";
+
+#[test]
+fn stops_at_breakpoints_and_shows_the_scope_chain() {
+    let (transcript, finished) = debug(PROGRAM, &[3], "continue\nenv\ncontinue\nc\n");
+
+    assert!(finished);
+    assert_eq!(
+        transcript,
+        concat!(
+            "line 1: fun add(a, b) {\n",
+            "(debug) Breakpoint, line 3: return sum;\n",
+            "(debug) scope 0:\n",
+            "  a = 1\n",
+            "  b = 2\n",
+            "  sum = 3\n",
+            "globals:\n",
+            "  add = <fn add>\n",
+            "(debug) Breakpoint, line 3: return sum;\n",
+            "(debug) 6\n",
+            "3\n",
+        )
+    );
+}
+
+#[test]
+fn steps_into_over_and_out_of_calls() {
+    let commands = "b 9\nc\nstep\nnext\nout\nc\n";
+    let (transcript, _) = debug(PROGRAM, &[], commands);
+
+    let paused: Vec<&str> = transcript
+        .lines()
+        .map(|line| line.trim_start_matches("(debug) "))
+        .filter(|line| line.contains("line"))
+        .collect();
+    assert_eq!(
+        paused,
+        [
+            "line 1: fun add(a, b) {",
+            "Breakpoint on line 9: print add(total, 3);",
+            "Breakpoint, line 9: print add(total, 3);",
+            "line 2: var sum = a + b;",
+            "line 3: return sum;",
+            "line 11: print total;",
+        ]
+    );
+    // `out` ran the rest of the block, printing on the way
+    assert!(transcript.contains("(debug) 6\nline 11: print total;\n"));
+}
+
+#[test]
+fn evaluates_expressions_in_the_paused_scope() {
+    let commands = "b 9\nc\np name + \"!\"\np total = 10\np total\np sum\np ;\nc\n";
+    let (transcript, _) = debug(PROGRAM, &[], commands);
+
+    let results: Vec<&str> = transcript.lines().skip(3).take(5).collect();
+    assert_eq!(
+        results,
+        [
+            "(debug) \"lox!\"",
+            "(debug) 10",
+            "(debug) 10",
+            "(debug) [line 1] Runtime Error: Undefined variable 'sum'.",
+            "(debug) [line 1] Parse Error: Expected valid primary expression. Received ';'.",
+        ]
+    );
+    // the assignment was real
+    assert!(transcript.ends_with("(debug) 13\n10\n"));
+}
+
+#[test]
+fn evaluates_this_inside_methods() {
+    let source = "class A {\n  init(x) {\n    this.x = x;\n  }\n}\nA(4);\n";
+    let (transcript, _) = debug(source, &[3], "c\np this\np x * 2\nc\n");
+
+    assert!(transcript.contains("(debug) A instance\n(debug) 8\n"));
+}
+
+#[test]
+fn quitting_stops_the_program() {
+    let (transcript, finished) = debug(PROGRAM, &[], "next\nquit\n");
+    assert!(!finished);
+    assert_eq!(
+        transcript,
+        "line 1: fun add(a, b) {\n(debug) line 6: var total = add(1, 2);\n(debug) "
+    );
+
+    // running out of commands does too
+    let (_, finished) = debug(PROGRAM, &[], "");
+    assert!(!finished);
+}
+
+#[test]
+fn hooks_see_every_statement_with_a_line() {
+    struct Lines(Vec<Option<usize>>);
+
+    impl ExecutionHook for Lines {
+        fn before_statement(
+            &mut self,
+            interpreter: &mut Interpreter,
+            stmt: &Stmt,
+        ) -> Result<(), RuntimeError> {
+            assert!(interpreter.call_depth() <= 1);
+            self.0.push(stmt.line());
+            Ok(())
+        }
+    }
+
+    let mut interpreter = Interpreter::new();
+    let statements = compile(&mut interpreter, PROGRAM);
+    interpreter.set_output(Sink::new(io::sink()));
+    let lines = Rc::new(RefCell::new(Lines(Vec::new())));
+    interpreter.set_hook(Some(lines.clone()));
+    interpreter.interpret(statements).expect("should run");
+
+    assert_eq!(
+        lines.borrow().0,
+        [
+            Some(1),
+            Some(6),
+            Some(2),
+            Some(3),
+            None,
+            Some(8),
+            Some(9),
+            Some(2),
+            Some(3),
+            Some(11)
+        ]
+    );
+}
@@ -0,0 +1,139 @@
+// `tokenize`/`parse --format`: the JSON dumps and the jlox-style S-expression printer.
+use my_ast_interpreter::json::Json;
+use my_ast_interpreter::{ast_printer, dump, Parser, Scanner, Stmt};
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let (tokens, errors) = Scanner::new(source.to_string()).scan_tokens();
+    assert!(errors.is_empty(), "should scan: {source}");
+
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "should parse: {source}");
+    statements
+}
+
+#[test]
+fn tokens_carry_type_lexeme_literal_and_position() {
+    let (tokens, _) = Scanner::new("var s =\n  \"a\\b\";".to_string()).scan_tokens();
+
+    assert_eq!(
+        dump::token(&tokens[3]).to_string(),
+        r#"{"type":"STRING","lexeme":"\"a\\b\"","literal":"a\\b","line":2,"column":3,"offset":10,"length":5}"#
+    );
+    assert_eq!(
+        dump::tokens(&tokens[..1]).to_string(),
+        r#"[{"type":"VAR","lexeme":"var","literal":null,"line":1,"column":1,"offset":0,"length":3}]"#
+    );
+}
+
+#[test]
+fn number_literals_are_json_numbers() {
+    let (tokens, _) = Scanner::new("12.5".to_string()).scan_tokens();
+
+    assert_eq!(
+        dump::token(&tokens[0]).get("literal"),
+        Some(&Json::Number(12.5))
+    );
+}
+
+// the positions of every token are left out here, `tokens_carry_...` covers them
+fn shape(json: &Json) -> String {
+    match json {
+        Json::Object(fields) if fields.iter().any(|(key, _)| key == "lexeme") => {
+            format!("'{}'", json.get("lexeme").expect("should have a lexeme"))
+        }
+        Json::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(key, value)| format!("{key}: {}", shape(value)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        Json::Array(values) => {
+            let values: Vec<String> = values.iter().map(shape).collect();
+            format!("[{}]", values.join(", "))
+        }
+        json => json.to_string(),
+    }
+}
+
+#[test]
+fn ast_dump_includes_params_and_call_arguments() {
+    let program = dump::program(&parse(
+        "fun add(a, b) { return a + b; }\nprint add(1, \"x\");",
+    ));
+
+    assert_eq!(
+        shape(&program),
+        concat!(
+            r#"[{type: "Function", name: '"add"', params: ['"a"', '"b"'], body: {type: "Block", statements: "#,
+            r#"[{type: "Return", keyword: '"return"', value: {type: "Binary", left: {type: "Variable", name: '"a"'}, "#,
+            r#"operator: '"+"', right: {type: "Variable", name: '"b"'}}}]}}, "#,
+            r#"{type: "Print", keyword: '"print"', expression: {type: "Call", callee: {type: "Variable", name: '"add"'}, paren: '")"', "#,
+            r#"arguments: [{type: "Literal", value: 1}, {type: "Literal", value: "x"}]}}]"#
+        )
+    );
+}
+
+#[test]
+fn ast_dump_marks_missing_children_null() {
+    let program = dump::program(&parse("if (true) return; class A {}"));
+
+    assert_eq!(
+        shape(&program),
+        concat!(
+            r#"[{type: "If", condition: {type: "Literal", value: true}, "#,
+            r#"thenBranch: {type: "Return", keyword: '"return"', value: null}, elseBranch: null}, "#,
+            r#"{type: "Class", name: '"A"', superclass: null, methods: []}]"#
+        )
+    );
+}
+
+#[test]
+fn s_expressions_show_how_the_parser_grouped_things() {
+    let printed = ast_printer::print(&parse(
+        r#"
+        print -1 + 2 * (3 - 4);
+        var a;
+        a = b.c(d, e)[0];
+        for (var i = 0; i < 3; i = i + 1) if (i == 1 or ok) break; else print "${i}!";
+        class B < A { init(x) { super.init(); this.x = x; } }
+        "#,
+    ));
+
+    assert_eq!(
+        printed,
+        concat!(
+            "(print (+ (- 1.0) (* 2.0 (group (- 3.0 4.0)))))\n",
+            "(var a)\n",
+            "(; (= a (index (call (. b c) d e) 0.0)))\n",
+            "(for (var i = 0.0) (< i 3.0) (= i (+ i 1.0)) (if-else (or (== i 1.0) ok) (break) (print (str i \"!\"))))\n",
+            "(class B < A (fun init (x) (block (; (call (super init))) (; (= this x x)))))\n",
+        )
+    );
+}
+
+#[test]
+fn json_strings_are_escaped() {
+    assert_eq!(
+        Json::string("tab\tquote\" nul\u{0}").to_string(),
+        r#""tab\tquote\" nul\u0000""#
+    );
+    assert_eq!(Json::Number(f64::NAN).to_string(), "null");
+}
+
+#[test]
+fn json_parses_what_it_prints() {
+    let json =
+        Json::parse(r#" {"a": [1, -2.5e1, true, null], "b\u00e9\n": "\ud83d\ude00", "c": {}} "#)
+            .expect("should parse");
+
+    assert_eq!(
+        json.to_string(),
+        "{\"a\":[1,-25,true,null],\"bé\\n\":\"😀\",\"c\":{}}"
+    );
+    assert_eq!(Json::parse(&json.to_string()).ok(), Some(json));
+
+    for invalid in ["", "[1,]", "{\"a\" 1}", "\"\\ud83d\"", "tru", "1 2"] {
+        assert!(Json::parse(invalid).is_err(), "{invalid} should not parse");
+    }
+}
@@ -0,0 +1,237 @@
+// `fmt`: formatting a parsed program gives canonical Lox that parses back to the same program.
+use my_ast_interpreter::{formatter, Comment, Parser, Scanner, Stmt};
+use std::{fs, path::Path};
+
+fn parse(source: &str) -> Option<Vec<Stmt>> {
+    parse_with_comments(source).map(|(statements, _)| statements)
+}
+
+fn parse_with_comments(source: &str) -> Option<(Vec<Stmt>, Vec<Comment>)> {
+    let (tokens, comments, errors) = Scanner::new(source.to_string()).scan_with_comments();
+    if !errors.is_empty() {
+        return None;
+    }
+
+    let (statements, errors) = Parser::new(tokens).parse();
+    errors.is_empty().then_some((statements, comments))
+}
+
+fn texts(comments: &[Comment]) -> Vec<&str> {
+    comments
+        .iter()
+        .map(|comment| comment.text.as_str())
+        .collect()
+}
+
+// formatting moves every token, so compare trees with their positions left out
+fn without_positions(statements: &[Stmt]) -> String {
+    let mut debug = format!("{statements:?}");
+
+    for (marker, end) in [("line: ", ','), ("span: Span {", '}')] {
+        while let Some(start) = debug.find(marker) {
+            let len = debug[start..].find(end).expect("should find end of field") + 1;
+            debug.replace_range(start..start + len, "");
+        }
+    }
+
+    debug
+}
+
+fn lox_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    for entry in fs::read_dir(dir).expect("should read dir") {
+        let path = entry.expect("should read dir entry").path();
+        if path.is_dir() {
+            lox_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn formatting_round_trips_every_conformance_script() {
+    let mut files = Vec::new();
+    lox_files(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox"),
+        &mut files,
+    );
+
+    for path in files {
+        let source = fs::read_to_string(&path).expect("should read lox file");
+        let Some((original, comments)) = parse_with_comments(&source) else {
+            continue; // scripts testing parse errors
+        };
+
+        let formatted = formatter::format_with_comments(&original, &comments);
+        let (reparsed, kept) = parse_with_comments(&formatted).unwrap_or_else(|| {
+            panic!(
+                "{}: formatted source should parse:\n{formatted}",
+                path.display()
+            )
+        });
+
+        assert_eq!(
+            without_positions(&original),
+            without_positions(&reparsed),
+            "{}: formatting changed the program:\n{formatted}",
+            path.display()
+        );
+        assert_eq!(
+            texts(&comments),
+            texts(&kept),
+            "{}: formatting lost comments:\n{formatted}",
+            path.display()
+        );
+        assert_eq!(
+            formatter::format_with_comments(&reparsed, &kept),
+            formatted,
+            "{}: formatting isn't idempotent",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn formats_canonically() {
+    let source = "class Point<Base{init(x,y){this.x=x;this.y=y;}sum(){return this.x+this.y;}}
+for(var i=0;i<2;i=i+1)print i;
+var f=fun(a){return [a,{\"k\":a}][0];};
+if(f(1)==1){print \"one ${f(1)}!\";}else if(false)print -(1+2);else{}
+try{throw \"x\";}catch(e){print e.message;}finally{}
+import \"lib.lox\" as lib;
+while(false)x=nil;
+for(;;)break;
+for(var j=0;j<1;){j=j+1;}
+for(;j<2;j=j+1)print j;";
+
+    let expected = "class Point < Base {
+  init(x, y) {
+    this.x = x;
+    this.y = y;
+  }
+
+  sum() {
+    return this.x + this.y;
+  }
+}
+
+for (var i = 0; i < 2; i = i + 1) print i;
+var f = fun (a) {
+  return [a, {\"k\": a}][0];
+};
+if (f(1) == 1) {
+  print \"one ${f(1)}!\";
+} else if (false) print -(1 + 2); else {}
+try {
+  throw \"x\";
+} catch (e) {
+  print e.message;
+} finally {}
+import \"lib.lox\" as lib;
+while (false) x = nil;
+for (;;) break;
+for (var j = 0; j < 1;) {
+  j = j + 1;
+}
+for (; j < 2; j = j + 1) print j;
+";
+
+    let statements = parse(source).expect("should parse");
+    assert_eq!(formatter::format(&statements), expected);
+}
+
+#[test]
+fn keeps_comments() {
+    let source = "// header
+var a = 1; // trailing
+fun f() { // opens the body
+  // before return
+  return a;
+}
+class A {
+  // a method
+  m() {}
+}
+print f(); // expect: 1
+// footer";
+
+    let expected = "// header
+var a = 1; // trailing
+
+fun f() {
+  // opens the body
+  // before return
+  return a;
+}
+
+class A {
+  // a method
+  m() {}
+}
+
+print f(); // expect: 1
+// footer
+";
+
+    let (statements, comments) = parse_with_comments(source).expect("should parse");
+    assert_eq!(
+        formatter::format_with_comments(&statements, &comments),
+        expected
+    );
+}
+
+#[test]
+fn keeps_comments_where_they_were() {
+    let source = "if (true) {
+  print 1;
+  // end of block
+}
+if (false) print 2;
+fun f() {
+  return 1;
+  // end of function body
+}
+// before a literal statement
+2;
+class A {
+  m() {}
+  // end of class body
+}
+try {
+  // empty block
+} finally {}";
+
+    let expected = "if (true) {
+  print 1;
+  // end of block
+}
+if (false) print 2;
+
+fun f() {
+  return 1;
+  // end of function body
+}
+
+// before a literal statement
+2;
+
+class A {
+  m() {}
+  // end of class body
+}
+
+try {
+  // empty block
+} finally {}
+";
+
+    let (statements, comments) = parse_with_comments(source).expect("should parse");
+    let formatted = formatter::format_with_comments(&statements, &comments);
+    assert_eq!(formatted, expected);
+
+    let (statements, comments) = parse_with_comments(&formatted).expect("should parse");
+    assert_eq!(
+        formatter::format_with_comments(&statements, &comments),
+        expected
+    );
+}
@@ -0,0 +1,101 @@
+// Execution limits: step budgets, call depth and cancelling a run from another thread.
+mod common;
+
+use common::try_run;
+use my_ast_interpreter::{Interpreter, RuntimeError, Sink};
+use std::{sync::atomic::Ordering, thread, time::Duration};
+
+#[test]
+fn step_limit_stops_infinite_loop() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_steps(Some(1000));
+
+    let err = try_run(&mut interpreter, "while (true) {}").expect_err("should hit the limit");
+
+    assert!(matches!(err, RuntimeError::Interrupted(_)), "{err:?}");
+    assert_eq!(
+        err.to_string(),
+        "Runtime Error: Execution step limit exceeded."
+    );
+}
+
+#[test]
+fn step_limit_is_not_catchable() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::from_fn(|line| panic!("unexpected output: {line}")));
+    interpreter.set_max_steps(Some(1000));
+
+    let err = try_run(
+        &mut interpreter,
+        "try { while (true) {} } catch (e) { print e; } finally { print 1; }",
+    )
+    .expect_err("should hit the limit");
+
+    assert!(matches!(err, RuntimeError::Interrupted(_)), "{err:?}");
+}
+
+#[test]
+fn step_limit_applies_per_interpret_call() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_steps(Some(10));
+
+    for _ in 0..3 {
+        try_run(&mut interpreter, "var a = 1; a = a + 1; print a;").expect("should stay in budget");
+    }
+}
+
+#[test]
+fn call_depth_limit_raises_stack_overflow() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_call_depth(50);
+
+    let err = try_run(
+        &mut interpreter,
+        "fun count(n) { if (n == 0) return 0; return count(n - 1); } count(60);",
+    )
+    .expect_err("should overflow");
+
+    assert_eq!(err.to_string(), "[line 1] Runtime Error: Stack overflow.");
+
+    try_run(&mut interpreter, "count(40);").expect("depth should be released after the error");
+}
+
+// test threads get a 2 MiB stack, far less than the default depth needs without growing it
+#[test]
+fn default_call_depth_fits_any_thread_stack() {
+    let mut interpreter = Interpreter::new();
+
+    let err = try_run(&mut interpreter, "fun r(n) { return r(n + 1); } r(0);")
+        .expect_err("should overflow");
+    assert_eq!(err.to_string(), "[line 1] Runtime Error: Stack overflow.");
+
+    let methods = "
+class A {
+  m(n) {
+    if (n == 0) return 0;
+    try {
+      return this.m(n - 1) + 1;
+    } catch (e) {
+      throw e;
+    }
+  }
+}
+A().m(998);";
+    try_run(&mut interpreter, methods).expect("should fit the default depth");
+}
+
+#[test]
+fn cancellation_flag_stops_run_from_another_thread() {
+    let mut interpreter = Interpreter::new();
+    let cancelled = interpreter.cancellation_flag();
+
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        cancelled.store(true, Ordering::Relaxed);
+    });
+
+    let err = try_run(&mut interpreter, "while (true) {}").expect_err("should be cancelled");
+    canceller.join().expect("canceller should not panic");
+
+    assert_eq!(err.to_string(), "Runtime Error: Execution cancelled.");
+}
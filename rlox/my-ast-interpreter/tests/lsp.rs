@@ -0,0 +1,408 @@
+// `lox-lsp`: a scripted client drives the server binary over stdio, the way an editor would.
+use my_ast_interpreter::json::Json;
+use my_ast_interpreter::lsp::{read_message, write_message};
+use std::io::BufReader;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///test.lox";
+
+struct Client {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: usize,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_lox-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("should start the language server");
+
+        let stdin = server.stdin.take().expect("should have piped stdin");
+        let stdout = BufReader::new(server.stdout.take().expect("should have piped stdout"));
+
+        let mut client = Client {
+            server,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+
+        let result = client.request("initialize", Json::object([]));
+        assert!(result.get("capabilities").is_some());
+        client.notify("initialized", Json::object([]));
+
+        client
+    }
+
+    fn send(&mut self, message: Json) {
+        write_message(&mut self.stdin, &message).expect("should write to the server");
+    }
+
+    fn receive(&mut self) -> Json {
+        let body = read_message(&mut self.stdout)
+            .expect("should read from the server")
+            .expect("server should still be running");
+        Json::parse(&body).expect("server should send valid JSON")
+    }
+
+    fn notify(&mut self, method: &str, params: Json) {
+        self.send(Json::object([
+            ("jsonrpc", Json::string("2.0")),
+            ("method", Json::string(method)),
+            ("params", params),
+        ]));
+    }
+
+    // the whole response, for checking errors
+    fn call(&mut self, method: &str, params: Json) -> Json {
+        self.next_id += 1;
+        self.send(Json::object([
+            ("jsonrpc", Json::string("2.0")),
+            ("id", self.next_id.into()),
+            ("method", Json::string(method)),
+            ("params", params),
+        ]));
+
+        let response = self.receive();
+        assert_eq!(response.get("id"), Some(&self.next_id.into()));
+        response
+    }
+
+    fn request(&mut self, method: &str, params: Json) -> Json {
+        let response = self.call(method, params);
+        response
+            .get("result")
+            .unwrap_or_else(|| panic!("{method} should succeed: {response}"))
+            .clone()
+    }
+
+    // opens (or replaces) the test document, returning the diagnostics published for it
+    fn open(&mut self, text: &str) -> Vec<Json> {
+        self.notify(
+            "textDocument/didOpen",
+            Json::object([(
+                "textDocument",
+                Json::object([
+                    ("uri", Json::string(URI)),
+                    ("languageId", Json::string("lox")),
+                    ("version", Json::Number(1.0)),
+                    ("text", Json::string(text)),
+                ]),
+            )]),
+        );
+
+        self.diagnostics()
+    }
+
+    fn diagnostics(&mut self) -> Vec<Json> {
+        let notification = self.receive();
+        assert_eq!(
+            notification.get("method"),
+            Some(&Json::string("textDocument/publishDiagnostics"))
+        );
+
+        let params = notification.get("params").expect("should have params");
+        assert_eq!(params.get("uri"), Some(&Json::string(URI)));
+        params
+            .get("diagnostics")
+            .and_then(Json::as_array)
+            .expect("should have diagnostics")
+            .to_vec()
+    }
+
+    fn at(&mut self, method: &str, line: usize, character: usize) -> Json {
+        self.request(method, position(line, character))
+    }
+
+    fn exit(mut self) -> i32 {
+        assert_eq!(self.request("shutdown", Json::Null), Json::Null);
+        self.notify("exit", Json::Null);
+
+        self.server
+            .wait()
+            .expect("server should exit")
+            .code()
+            .expect("should have an exit code")
+    }
+}
+
+fn position(line: usize, character: usize) -> Json {
+    Json::object([
+        ("textDocument", Json::object([("uri", Json::string(URI))])),
+        (
+            "position",
+            Json::object([("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+// `line:start-end` of a `Location` or `Range`, to keep expectations short
+fn span(json: &Json) -> String {
+    let range = json.get("range").unwrap_or(json);
+    let field = |point: &str, field: &str| {
+        range
+            .get(point)
+            .and_then(|point| point.get(field))
+            .and_then(Json::as_usize)
+            .expect("should be a range")
+    };
+
+    assert_eq!(field("start", "line"), field("end", "line"));
+    format!(
+        "{}:{}-{}",
+        field("start", "line"),
+        field("start", "character"),
+        field("end", "character")
+    )
+}
+
+fn spans(json: &Json) -> Vec<String> {
+    json.as_array()
+        .expect("should be an array")
+        .iter()
+        .map(span)
+        .collect()
+}
+
+const PROGRAM: &str = "\
+fun add(a, b) {
+  var sum = a + b;
+  return sum;
+}
+
+var total = add(1, 2);
+{
+  var count = add(total, 3);
+  var total = count;
+  print total;
+}
+print total;
+";
+
+#[test]
+fn publishes_diagnostics_on_open_and_change() {
+    let mut client = Client::start();
+
+    let diagnostics = client.open("var a = 1;\nprint a +;\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(span(&diagnostics[0]), "1:9-10");
+    assert_eq!(diagnostics[0].get("severity"), Some(&Json::Number(1.0)));
+    assert_eq!(
+        diagnostics[0].get("message"),
+        Some(&Json::string(
+            "Parse Error: Expected valid primary expression. Received ';'."
+        ))
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        Json::object([
+            (
+                "textDocument",
+                Json::object([("uri", Json::string(URI)), ("version", Json::Number(2.0))]),
+            ),
+            (
+                "contentChanges",
+                Json::Array(vec![Json::object([(
+                    "text",
+                    Json::string("{\n  var unused = 1;\n}\n"),
+                )])]),
+            ),
+        ]),
+    );
+
+    let diagnostics = client.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(span(&diagnostics[0]), "1:6-12");
+    assert_eq!(diagnostics[0].get("severity"), Some(&Json::Number(2.0)));
+
+    client.notify(
+        "textDocument/didClose",
+        Json::object([("textDocument", Json::object([("uri", Json::string(URI))]))]),
+    );
+    assert!(client.diagnostics().is_empty());
+
+    assert_eq!(client.exit(), 0);
+}
+
+#[test]
+fn goes_to_definitions_through_scopes() {
+    let mut client = Client::start();
+    assert!(client.open(PROGRAM).is_empty());
+
+    // the parameter `a` in `a + b`
+    assert_eq!(span(&client.at("textDocument/definition", 1, 12)), "0:8-9");
+    // `total` is still the global one when it's passed to `add`
+    assert_eq!(span(&client.at("textDocument/definition", 7, 18)), "5:4-9");
+    // `print total;` inside the block reads the shadowing local
+    assert_eq!(span(&client.at("textDocument/definition", 9, 8)), "8:6-11");
+    // a global function, called before the cursor's line
+    assert_eq!(span(&client.at("textDocument/definition", 7, 14)), "0:4-7");
+    // builtins and whitespace have nowhere to go
+    assert_eq!(client.at("textDocument/definition", 4, 0), Json::Null);
+
+    client.exit();
+}
+
+#[test]
+fn finds_references_to_the_same_declaration() {
+    let mut client = Client::start();
+    client.open(PROGRAM);
+
+    assert_eq!(
+        spans(&client.at("textDocument/references", 5, 6)),
+        ["5:4-9", "7:18-23", "11:6-11"]
+    );
+    assert_eq!(
+        spans(&client.at("textDocument/references", 0, 5)),
+        ["0:4-7", "5:12-15", "7:14-17"]
+    );
+
+    let mut params = position(2, 10);
+    if let Json::Object(fields) = &mut params {
+        fields.push((
+            "context".to_string(),
+            Json::object([("includeDeclaration", Json::Bool(false))]),
+        ));
+    }
+    assert_eq!(
+        spans(&client.request("textDocument/references", params)),
+        ["2:9-12"]
+    );
+
+    client.exit();
+}
+
+#[test]
+fn hover_shows_function_parameters() {
+    let mut client = Client::start();
+    client.open(&format!(
+        "{PROGRAM}class Point {{\n  init(x, y) {{}}\n}}\nvar scale = fun (p, by) {{}};\nscale(Point(1, 2), 3);\n"
+    ));
+
+    let hover = client.at("textDocument/hover", 5, 13);
+    assert_eq!(span(&hover), "5:12-15");
+    assert_eq!(
+        hover
+            .get("contents")
+            .and_then(|contents| contents.get("value")),
+        Some(&Json::string("```lox\nfun add(a, b)\n```"))
+    );
+
+    let value = |hover: Json| {
+        hover
+            .get("contents")
+            .and_then(|contents| contents.get("value"))
+            .and_then(Json::as_str)
+            .map(|value| value.lines().nth(1).unwrap_or_default().to_string())
+    };
+
+    assert_eq!(
+        value(client.at("textDocument/hover", 13, 2)).as_deref(),
+        Some("Point.init(x, y)")
+    );
+    assert_eq!(
+        value(client.at("textDocument/hover", 16, 1)).as_deref(),
+        Some("fun scale(p, by)")
+    );
+    assert_eq!(
+        value(client.at("textDocument/hover", 16, 7)).as_deref(),
+        Some("class Point")
+    );
+    assert_eq!(
+        value(client.at("textDocument/hover", 1, 13)).as_deref(),
+        Some("(parameter) a")
+    );
+    assert_eq!(client.at("textDocument/hover", 4, 0), Json::Null);
+
+    client.exit();
+}
+
+#[test]
+fn lists_top_level_declarations() {
+    let mut client = Client::start();
+    client.open("import \"lib.lox\" as lib;\nvar x;\nfun f(a) { var inner; }\nclass A < B {\n  init() {}\n  m(n) {}\n}\n");
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        Json::object([("textDocument", Json::object([("uri", Json::string(URI))]))]),
+    );
+
+    let summary = |symbol: &Json| {
+        format!(
+            "{} {} {}",
+            symbol.get("kind").expect("should have a kind"),
+            symbol
+                .get("name")
+                .and_then(Json::as_str)
+                .expect("should have a name"),
+            symbol.get("detail").expect("should have a detail"),
+        )
+    };
+
+    let symbols = symbols.as_array().expect("should be an array");
+    assert_eq!(
+        symbols.iter().map(summary).collect::<Vec<_>>(),
+        [
+            "2 lib \"\\\"lib.lox\\\"\"",
+            "13 x null",
+            "12 f \"(a)\"",
+            "5 A \"< B\""
+        ]
+    );
+    assert_eq!(span(&symbols[3]), "3:6-7");
+
+    let methods = symbols[3]
+        .get("children")
+        .and_then(Json::as_array)
+        .expect("class should have children");
+    assert_eq!(
+        methods.iter().map(summary).collect::<Vec<_>>(),
+        ["9 init \"()\"", "6 m \"(n)\""]
+    );
+
+    client.exit();
+}
+
+#[test]
+fn reports_protocol_errors() {
+    let mut client = Client::start();
+
+    let response = client.call("textDocument/formatting", Json::object([]));
+    assert_eq!(
+        response.get("error").and_then(|error| error.get("code")),
+        Some(&Json::Number(-32601.0))
+    );
+
+    // the document was never opened
+    let response = client.call("textDocument/hover", position(0, 0));
+    assert_eq!(
+        response.get("error").and_then(|error| error.get("code")),
+        Some(&Json::Number(-32602.0))
+    );
+
+    write_message(&mut client.stdin, &Json::string("placeholder")).expect("should write");
+    let body = "{\"jsonrpc\": \"2.0\", \"id\": ";
+    std::io::Write::write_all(
+        &mut client.stdin,
+        format!("Content-Length: {}\r\n\r\n{body}", body.len()).as_bytes(),
+    )
+    .expect("should write");
+
+    // a bare string is valid JSON but not a message, so only the truncated one gets a reply
+    let response = client.receive();
+    assert_eq!(response.get("id"), Some(&Json::Null));
+    assert_eq!(
+        response.get("error").and_then(|error| error.get("code")),
+        Some(&Json::Number(-32700.0))
+    );
+
+    // exiting without a shutdown request is an error
+    client.notify("exit", Json::Null);
+    let status = client.server.wait().expect("server should exit");
+    assert_eq!(status.code(), Some(1));
+}
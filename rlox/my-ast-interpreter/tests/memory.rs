@@ -0,0 +1,129 @@
+// Cycle collection: closures stored in the environments they capture are reclaimed.
+mod common;
+
+use common::{run, Buffer};
+use my_ast_interpreter::{gc, ExprValue, Interpreter, Sink};
+
+// each call leaves behind an env holding a function that closes over that same env
+const RECURSIVE_CLOSURES: &str = "
+fun make() {
+  fun recurse(n) {
+    if (n > 0) return recurse(n - 1);
+    return n;
+  }
+  return recurse;
+}
+
+for (var i = 0; i < 100; i = i + 1) {
+  make()(3);
+}
+";
+
+#[test]
+fn collect_frees_closures_that_went_out_of_scope() {
+    let mut interpreter = Interpreter::new();
+    gc::collect();
+    let baseline = gc::live_environments();
+
+    run(&mut interpreter, RECURSIVE_CLOSURES);
+    assert!(gc::live_environments() >= baseline + 100);
+
+    assert!(gc::collect() >= 100);
+    assert_eq!(gc::live_environments(), baseline);
+}
+
+#[test]
+fn collect_keeps_reachable_closures() {
+    let buffer = Buffer::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::new(buffer.clone()));
+
+    run(
+        &mut interpreter,
+        "
+        fun counter() {
+          var count = 0;
+          fun increment() {
+            count = count + 1;
+            return count;
+          }
+          return increment;
+        }
+        var next = counter();
+        var list = [counter()];
+        class Box { init(f) { this.f = f; } }
+        var box = Box(counter());
+        next();
+        ",
+    );
+
+    gc::collect();
+
+    run(
+        &mut interpreter,
+        "print next(); print list[0](); print box.f(); print box.f();",
+    );
+
+    assert_eq!(buffer.contents(), "2\n1\n1\n2\n");
+}
+
+#[test]
+fn collect_during_a_run_keeps_the_running_code_alive() {
+    let buffer = Buffer::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::new(buffer.clone()));
+    interpreter.define_native("collect", 0, |_, _| {
+        Ok(ExprValue::Number(gc::collect() as f64))
+    });
+
+    run(
+        &mut interpreter,
+        "
+        fun outer(n) {
+          var local = \"kept ${n}\";
+          fun inner() {
+            collect();
+            return local;
+          }
+          var temp = [inner];
+          collect();
+          return temp[0]();
+        }
+        print outer(1);
+        print outer(2);
+        ",
+    );
+
+    assert_eq!(buffer.contents(), "kept 1\nkept 2\n");
+}
+
+#[test]
+fn long_running_loops_collect_automatically() {
+    let mut interpreter = Interpreter::new();
+
+    run(
+        &mut interpreter,
+        "
+        fun make() {
+          fun self() { return self; }
+          return self;
+        }
+        for (var i = 0; i < 20000; i = i + 1) make();
+        ",
+    );
+
+    assert!(gc::live_environments() < 5000);
+}
+
+#[test]
+fn dropped_interpreter_is_collected() {
+    gc::collect();
+    let baseline = gc::live_environments();
+
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "fun f() { return f; } var list = [f];");
+    drop(interpreter);
+
+    gc::collect();
+    assert_eq!(gc::live_environments(), baseline);
+}
@@ -0,0 +1,66 @@
+// Imported modules: errors raised in a module point into that module's own source.
+mod common;
+
+use common::try_run;
+use my_ast_interpreter::diagnostic::{render_in_file, Diagnostic};
+use my_ast_interpreter::Interpreter;
+use std::{fs, path::PathBuf};
+
+// an interpreter running `main.lox` in a fresh directory holding `files`
+fn project(name: &str, files: &[(&str, &str)]) -> (Interpreter, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("rlox-modules-{}-{name}", std::process::id()));
+    fs::create_dir_all(&dir).expect("should create project dir");
+    for (file, source) in files {
+        fs::write(dir.join(file), source).expect("should write module");
+    }
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_path(dir.join("main.lox"));
+    (interpreter, dir)
+}
+
+#[test]
+fn runtime_errors_render_against_the_module_they_came_from() {
+    let bad = "fun boom() {\n  return nil + 1;\n}\n";
+    let (mut interpreter, dir) = project("runtime", &[("bad.lox", bad)]);
+
+    let err = try_run(&mut interpreter, "import \"bad.lox\" as b;\nb.boom();\n")
+        .expect_err("should fail in the module");
+    fs::remove_dir_all(dir).expect("should remove project dir");
+
+    let (_, span) = err.location().expect("should have a location");
+    let (path, source) = interpreter
+        .module_source(span)
+        .expect("should come from the module");
+    assert_eq!((path, source), ("bad.lox", bad));
+
+    assert_eq!(
+        render_in_file(path, source, &err),
+        concat!(
+            "[line 2] Runtime Error: Operands must be two numbers or two strings.\n",
+            "  --> bad.lox:2:14\n",
+            " 2 |   return nil + 1;\n",
+            "   |              ^",
+        )
+    );
+}
+
+#[test]
+fn every_compile_error_in_a_module_is_reported() {
+    let (mut interpreter, dir) = project("compile", &[("broken.lox", "var = 1;\nprint ;\n")]);
+
+    let err = try_run(&mut interpreter, "import \"broken.lox\";")
+        .expect_err("should fail to compile the module");
+    fs::remove_dir_all(dir).expect("should remove project dir");
+
+    assert_eq!(
+        err.to_string(),
+        concat!(
+            "[line 1] Runtime Error: In module 'broken.lox': [line 1] Parse Error at '=': Expect variable name.\n",
+            "In module 'broken.lox': [line 2] Parse Error: Expected valid primary expression. Received ';'.",
+        )
+    );
+    // raised at the `import`, so it renders against the importing file
+    let (_, span) = err.location().expect("should have a location");
+    assert!(interpreter.module_source(span).is_none());
+}
@@ -0,0 +1,69 @@
+// Embedding API: running scripts in-process with output captured instead of printed.
+mod common;
+
+use common::{run, try_run, Buffer};
+use my_ast_interpreter::{Interpreter, Sink};
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn print_writes_to_custom_writer() {
+    let buffer = Buffer::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::new(buffer.clone()));
+
+    run(
+        &mut interpreter,
+        "print 1 + 2; fun greet(name) { print \"hi ${name}\"; } greet(\"lox\");",
+    );
+
+    assert_eq!(buffer.contents(), "3\nhi lox\n");
+}
+
+#[test]
+fn print_calls_callback_once_per_line() {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let captured = lines.clone();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::from_fn(move |line| {
+        captured.borrow_mut().push(line.to_string())
+    }));
+
+    run(
+        &mut interpreter,
+        "for (var i = 0; i < 3; i = i + 1) print i;",
+    );
+
+    assert_eq!(*lines.borrow(), vec!["0\n", "1\n", "2\n"]);
+}
+
+#[test]
+fn warnings_and_uncaught_errors_go_to_the_diagnostics_sink() {
+    let output = Buffer::default();
+    let diagnostics = Buffer::default();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Sink::new(output.clone()));
+    interpreter.set_diagnostics(Sink::new(diagnostics.clone()));
+
+    let source = "var a = 1;\na = a;\nprint \"out\";\nnil + 1;";
+    try_run(&mut interpreter, source).expect_err("should fail");
+
+    assert_eq!(output.contents(), "out\n");
+    assert_eq!(
+        diagnostics.contents(),
+        concat!(
+            "[line 2] Warning: 'a' is assigned to itself.\n",
+            " 2 | a = a;\n",
+            "   | ^\n",
+            "[line 4] Runtime Error: Operands must be two numbers or two strings.\n",
+            " 4 | nil + 1;\n",
+            "   |     ^\n",
+        )
+    );
+
+    // caught errors aren't diagnostics
+    let reported = diagnostics.contents();
+    run(&mut interpreter, "try { nil + 1; } catch (e) {}");
+    assert_eq!(diagnostics.contents(), reported);
+}
@@ -0,0 +1,74 @@
+// `repl`: a session piped through the binary, one entry per line.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// stdout with the prompts left out, and stderr; the repl ends its output with a newline at EOF
+fn session(input: &str) -> (String, String) {
+    session_with(&[], input)
+}
+
+fn session_with(flags: &[&str], input: &str) -> (String, String) {
+    let mut repl = Command::new(env!("CARGO_BIN_EXE_my-ast-interpreter"))
+        .arg("repl")
+        .args(flags)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("should start the repl");
+
+    repl.stdin
+        .take()
+        .expect("should have piped stdin")
+        .write_all(input.as_bytes())
+        .expect("should write the session");
+
+    let output = repl.wait_with_output().expect("repl should exit");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout).replace("> ", "");
+    (stdout, String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+#[test]
+fn entries_share_globals_and_echo_expressions() {
+    let (stdout, stderr) = session("var a = 1;\nfun inc() { a = a + 1; }\ninc();\na * 10\n");
+
+    assert_eq!(stdout, "nil\n20\n\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn entries_at_the_same_positions_resolve_separately() {
+    // the global `x` read on the second line sits at the same line and offset as the block's
+    // local `x` on the first
+    let (stdout, stderr) = session("{ var x = 1; print x; }\nvar x = 5; print   x;\n");
+
+    assert_eq!(stdout, "1\n5\n\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn errors_leave_the_session_running() {
+    let (stdout, stderr) = session("print nil + 1;\nprint 2;\n");
+
+    assert_eq!(stdout, "2\n\n");
+    assert!(
+        stderr.starts_with("[line 1] Runtime Error: Operands must be"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn warnings_as_errors_rejects_the_entry() {
+    let (stdout, stderr) = session_with(
+        &["--warnings-as-errors"],
+        "var a = 1;\na = a; print a;\nprint a;\n",
+    );
+
+    assert_eq!(stdout, "1\n\n");
+    assert!(
+        stderr.starts_with("[line 1] Warning: 'a' is assigned to itself."),
+        "{stderr}"
+    );
+}
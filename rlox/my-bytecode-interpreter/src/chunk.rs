@@ -33,6 +33,12 @@ pub struct Chunk {
     pub lines: Vec<usize>,
 }
 
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Chunk {
     pub fn new() -> Self {
         Self {
@@ -50,7 +56,6 @@ impl Chunk {
     // returns const's idx in vec
     pub fn add_constant(&mut self, value: Value) -> u8 {
         self.constants.push(value);
-        let idx = (self.constants.len() - 1) as u8;
-        idx
+        (self.constants.len() - 1) as u8
     }
 }
@@ -1,42 +1,54 @@
 use crate::{Chunk, OpCode, Value};
+use std::io::{self, Write};
 
-pub fn disassemble(chunk: &Chunk, name: &str) {
-    println!("== {} ==\n", name);
+pub fn disassemble(out: &mut dyn Write, chunk: &Chunk, name: &str) -> io::Result<()> {
+    writeln!(out, "== {} ==\n", name)?;
 
     let mut offset = 0;
     while offset < chunk.code.len() {
-        offset = disassemble_instruction(chunk, offset);
+        offset = disassemble_instruction(out, chunk, offset)?;
     }
+
+    Ok(())
 }
 
-pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
-    print!("{:04} ", offset);
+pub fn disassemble_instruction(
+    out: &mut dyn Write,
+    chunk: &Chunk,
+    offset: usize,
+) -> io::Result<usize> {
+    write!(out, "{:04} ", offset)?;
 
     if offset > 0 && chunk.lines[offset] == chunk.lines[offset - 1] {
-        print!("  | ")
+        write!(out, "  | ")?;
     } else {
-        print!("{:4} ", chunk.lines[offset]);
+        write!(out, "{:4} ", chunk.lines[offset])?;
     }
 
     let instruction = OpCode::from(chunk.code[offset]);
 
     match instruction {
-        OpCode::Return => simple_instruction(&"OP_RETURN", offset),
-        OpCode::Constant => constant_instruction(&"OP_CONSTANT", chunk, offset),
-        OpCode::Negate => simple_instruction("OP_NEGATE", offset),
-        OpCode::Add => simple_instruction("OP_ADD", offset),
-        OpCode::Subtract => simple_instruction("OP_SUBTRACT", offset),
-        OpCode::Multiply => simple_instruction("OP_MULTIPLY", offset),
-        OpCode::Divide => simple_instruction("OP_DIVIDE", offset),
+        OpCode::Return => simple_instruction(out, "OP_RETURN", offset),
+        OpCode::Constant => constant_instruction(out, "OP_CONSTANT", chunk, offset),
+        OpCode::Negate => simple_instruction(out, "OP_NEGATE", offset),
+        OpCode::Add => simple_instruction(out, "OP_ADD", offset),
+        OpCode::Subtract => simple_instruction(out, "OP_SUBTRACT", offset),
+        OpCode::Multiply => simple_instruction(out, "OP_MULTIPLY", offset),
+        OpCode::Divide => simple_instruction(out, "OP_DIVIDE", offset),
     }
 }
 
-fn simple_instruction(name: &str, offset: usize) -> usize {
-    println!("{}", name);
-    offset + 1
+fn simple_instruction(out: &mut dyn Write, name: &str, offset: usize) -> io::Result<usize> {
+    writeln!(out, "{}", name)?;
+    Ok(offset + 1)
 }
 
-fn constant_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
+fn constant_instruction(
+    out: &mut dyn Write,
+    name: &str,
+    chunk: &Chunk,
+    offset: usize,
+) -> io::Result<usize> {
     let constant = chunk.code[offset + 1];
     if constant as usize >= chunk.constants.len() {
         panic!(
@@ -46,14 +58,14 @@ fn constant_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
         );
     }
 
-    print!("{:-16} {:4} '", name, constant);
-    print_value(&chunk.constants[constant as usize]);
-    println!("'");
-    offset + 2
+    write!(out, "{:-16} {:4} '", name, constant)?;
+    print_value(out, &chunk.constants[constant as usize])?;
+    writeln!(out, "'")?;
+    Ok(offset + 2)
 }
 
-pub fn print_value(value: &Value) {
+pub fn print_value(out: &mut dyn Write, value: &Value) -> io::Result<()> {
     match value {
-        Value::Number(n) => print!("{}", n),
+        Value::Number(n) => write!(out, "{}", n),
     }
 }
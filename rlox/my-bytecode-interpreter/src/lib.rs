@@ -1,10 +1,12 @@
 mod chunk;
 mod debug;
+mod sink;
 mod value;
 mod vm;
 
 pub use chunk::{Chunk, OpCode};
 pub use debug::{disassemble, disassemble_instruction, print_value};
+pub use sink::Sink;
 pub use value::Value;
 pub use vm::Vm;
 
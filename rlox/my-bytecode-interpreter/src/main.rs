@@ -1,4 +1,5 @@
 use my_bytecode_interpreter::{disassemble, Chunk, OpCode, Value, Vm};
+use std::io;
 
 fn main() {
     let mut vm = Vm::new();
@@ -11,7 +12,7 @@ fn main() {
     let mut constant = chunk.add_constant(Value::Number(1.2));
 
     chunk.write_chunk(OpCode::Constant as u8, line);
-    chunk.write_chunk(constant as u8, line);
+    chunk.write_chunk(constant, line);
     constant = chunk.add_constant(Value::Number(3.4));
     chunk.write_chunk(OpCode::Constant as u8, line);
     chunk.write_chunk(constant, line);
@@ -22,7 +23,7 @@ fn main() {
     chunk.write_chunk(OpCode::Divide as u8, line);
     chunk.write_chunk(OpCode::Negate as u8, line);
     chunk.write_chunk(OpCode::Return as u8, line);
-    disassemble(&chunk, "test chunk");
+    disassemble(&mut io::stdout(), &chunk, "test chunk").expect("should write disassembly");
 
     if let Err(e) = vm.interpret(&chunk) {
         eprintln!("Error interpreting with VM: {e:?}");
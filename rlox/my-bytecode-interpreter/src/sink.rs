@@ -0,0 +1,36 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Where the vm writes program output or diagnostics (traces, errors). Clones share the writer.
+#[derive(Clone)]
+pub struct Sink(Rc<RefCell<dyn Write>>);
+
+impl Sink {
+    pub fn new(writer: impl Write + 'static) -> Self {
+        Sink(Rc::new(RefCell::new(writer)))
+    }
+
+    pub fn stdout() -> Self {
+        Sink::new(io::stdout())
+    }
+
+    pub fn stderr() -> Self {
+        Sink::new(io::stderr())
+    }
+
+    // the vm treats output as best-effort: a failing sink must not change how a chunk runs
+    pub(crate) fn emit(&self, line: &str) {
+        let mut writer = self.0.borrow_mut();
+        let _ = writeln!(writer, "{line}").and_then(|_| writer.flush());
+    }
+}
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sink")
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{disassemble_instruction, print_value, Chunk, OpCode, Value, DEBUG_TRACE_EXECUTION};
+use std::io::Write;
+
+use crate::{
+    disassemble_instruction, print_value, Chunk, OpCode, Sink, Value, DEBUG_TRACE_EXECUTION,
+};
 
 pub const STACK_MAX: usize = 256;
 
@@ -14,6 +18,14 @@ pub struct Vm<'a> {
     ip: *const u8,
     stack: [Value; STACK_MAX],
     stack_top: *mut Value,
+    output: Sink,      // results of executed chunks
+    diagnostics: Sink, // execution traces and runtime errors
+}
+
+impl Default for Vm<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Vm<'a> {
@@ -23,12 +35,25 @@ impl<'a> Vm<'a> {
             ip: std::ptr::null(),
             stack: [Value::default(); STACK_MAX],
             stack_top: std::ptr::null_mut(),
+            output: Sink::stdout(),
+            diagnostics: Sink::stderr(),
         }
     }
 
+    pub fn set_output(&mut self, sink: Sink) {
+        self.output = sink;
+    }
+
+    pub fn set_diagnostics(&mut self, sink: Sink) {
+        self.diagnostics = sink;
+    }
+
     pub fn init(&mut self) {
         self.reset_stack();
-        println!("VM init'd: stack_top after reset: {:?}", self.stack_top);
+        self.diagnostics.emit(&format!(
+            "VM init'd: stack_top after reset: {:?}",
+            self.stack_top
+        ));
     }
 
     pub fn reset_stack(&mut self) {
@@ -69,7 +94,7 @@ impl<'a> Vm<'a> {
         match res {
             Ok(res) => Ok(res),
             Err(e) => {
-                eprintln!("Runtime error: {:?}", e);
+                self.diagnostics.emit(&format!("Runtime error: {:?}", e));
                 Err(e)
             }
         }
@@ -78,25 +103,7 @@ impl<'a> Vm<'a> {
     fn run(&mut self) -> Result<InterpretResult, InterpretResult> {
         loop {
             if DEBUG_TRACE_EXECUTION {
-                print!("          ");
-
-                let stack_size = unsafe { self.stack_top.offset_from(self.stack.as_ptr()) };
-
-                if stack_size >= 0 && stack_size <= STACK_MAX as isize {
-                    for slot in &self.stack[..stack_size as usize] {
-                        print!("[ ");
-                        print_value(slot);
-                        print!(" ]");
-                    }
-                    println!("");
-                } else {
-                    println!("STACK ERROR (size: {}", stack_size);
-                }
-
-                if let Some(chunk) = self.chunk {
-                    let offset = unsafe { self.ip.offset_from(chunk.code.as_ptr()) } as usize;
-                    disassemble_instruction(chunk, offset);
-                }
+                self.trace();
                 // if let Some(chunk) = self.chunk {
                 //     let code_start = chunk.code.as_ptr();
                 //     if code_start.is_null() {
@@ -118,8 +125,8 @@ impl<'a> Vm<'a> {
 
             match OpCode::from(instruction) {
                 OpCode::Return => {
-                    print_value(&self.pop()?);
-                    println!("");
+                    let value = self.pop()?;
+                    self.output.emit(&value.to_string());
                     return Ok(InterpretResult::Ok);
                 }
                 OpCode::Constant => {
@@ -150,6 +157,36 @@ impl<'a> Vm<'a> {
         }
     }
 
+    // stack contents and the next instruction, written to `diagnostics`
+    fn trace(&self) {
+        let mut trace = Vec::new();
+        let stack_size = unsafe { self.stack_top.offset_from(self.stack.as_ptr()) };
+
+        // writes into a Vec can't fail
+        write!(trace, "          ").expect("should write trace to buffer");
+        if stack_size >= 0 && stack_size <= STACK_MAX as isize {
+            for slot in &self.stack[..stack_size as usize] {
+                write!(trace, "[ ").expect("should write trace to buffer");
+                print_value(&mut trace, slot).expect("should write trace to buffer");
+                write!(trace, " ]").expect("should write trace to buffer");
+            }
+            writeln!(trace).expect("should write trace to buffer");
+        } else {
+            writeln!(trace, "STACK ERROR (size: {}", stack_size)
+                .expect("should write trace to buffer");
+        }
+
+        if let Some(chunk) = self.chunk {
+            let offset = unsafe { self.ip.offset_from(chunk.code.as_ptr()) } as usize;
+            disassemble_instruction(&mut trace, chunk, offset)
+                .expect("should write trace to buffer");
+        }
+
+        for line in String::from_utf8_lossy(&trace).lines() {
+            self.diagnostics.emit(line);
+        }
+    }
+
     fn read_byte(&mut self) -> u8 {
         unsafe {
             let byte = *self.ip;
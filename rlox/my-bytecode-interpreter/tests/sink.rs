@@ -0,0 +1,59 @@
+use my_bytecode_interpreter::{Chunk, OpCode, Sink, Value, Vm};
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+// text written to a sink, shared with the test
+#[derive(Clone, Default)]
+struct Text(Rc<RefCell<String>>);
+
+impl Write for Text {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().push_str(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// -(1.2 + 3.4)
+fn chunk() -> Chunk {
+    let mut chunk = Chunk::new();
+
+    for n in [1.2, 3.4] {
+        let constant = chunk.add_constant(Value::Number(n));
+        chunk.write_chunk(OpCode::Constant as u8, 1);
+        chunk.write_chunk(constant, 1);
+    }
+    chunk.write_chunk(OpCode::Add as u8, 1);
+    chunk.write_chunk(OpCode::Negate as u8, 1);
+    chunk.write_chunk(OpCode::Return as u8, 1);
+
+    chunk
+}
+
+#[test]
+fn output_and_traces_go_to_their_own_sinks() {
+    let output = Text::default();
+    let traces = Text::default();
+
+    let mut vm = Vm::new();
+    vm.set_output(Sink::new(output.clone()));
+    vm.set_diagnostics(Sink::new(traces.clone()));
+    vm.init();
+
+    let chunk = chunk();
+    vm.interpret(&chunk).expect("should run chunk");
+
+    assert_eq!(*output.0.borrow(), "-4.6\n");
+
+    let traces = traces.0.borrow();
+    let traces: Vec<&str> = traces.lines().collect();
+    assert!(traces[0].starts_with("VM init'd"));
+    assert!(traces.iter().any(|line| line.contains("OP_ADD")));
+    assert!(traces.iter().any(|line| line.contains("[ 1.2 ][ 3.4 ]")));
+}